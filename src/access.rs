@@ -0,0 +1,57 @@
+use axum::http::{header::AUTHORIZATION, HeaderMap, StatusCode};
+
+use crate::auth::bearer_matches;
+use crate::config::ApiKeyConfig;
+use crate::state::AppState;
+use crate::tags::{tags_match, Tags};
+
+/// The endpoints a scoped API key may see: listed nicknames plus any endpoint carrying all of `tags`
+#[derive(Debug, Clone)]
+pub struct Scope {
+    nicknames: Vec<String>,
+    tags: Vec<(String, String)>,
+}
+
+impl Scope {
+    fn from_key(key: &ApiKeyConfig) -> Self {
+        Scope {
+            nicknames: key.nicknames.clone(),
+            tags: key.tags.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+        }
+    }
+
+    pub fn allows(&self, nickname: &str, tags: &Tags) -> bool {
+        self.nicknames.iter().any(|n| n == nickname) || (!self.tags.is_empty() && tags_match(tags, &self.tags))
+    }
+}
+
+/// Whether `scope` (None = unrestricted) allows an endpoint
+pub fn allowed(scope: &Option<Scope>, nickname: &str, tags: &Tags) -> bool {
+    scope.as_ref().map_or(true, |scope| scope.allows(nickname, tags))
+}
+
+// Work out what the caller may see. The admin token and anonymous callers on a public
+// instance see everything; a scoped key sees its subset; anything else is rejected.
+pub fn resolve_scope(state: &AppState, headers: &HeaderMap) -> Result<Option<Scope>, StatusCode> {
+    if !headers.contains_key(AUTHORIZATION) {
+        return if state.config.access.public() {
+            Ok(None)
+        } else {
+            Err(StatusCode::UNAUTHORIZED)
+        };
+    }
+
+    if let Some(admin) = &state.config.admin {
+        if bearer_matches(headers, &admin.token) {
+            return Ok(None);
+        }
+    }
+    state
+        .config
+        .access
+        .keys
+        .iter()
+        .find(|key| bearer_matches(headers, &key.token))
+        .map(|key| Some(Scope::from_key(key)))
+        .ok_or(StatusCode::UNAUTHORIZED)
+}
@@ -0,0 +1,47 @@
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use std::collections::HashMap;
+
+use crate::auth::bearer_matches;
+use crate::models::ErrorRecord;
+use crate::state::AppState;
+use crate::storage::{scan_prefix, ERRORS_PREFIX};
+
+// Admin routes are disabled (404) unless `[admin] token` is configured
+pub fn require_admin(state: &AppState, headers: &HeaderMap) -> Result<(), StatusCode> {
+    match &state.config.admin {
+        None => Err(StatusCode::NOT_FOUND),
+        Some(admin) if bearer_matches(headers, &admin.token) => Ok(()),
+        Some(_) => Err(StatusCode::UNAUTHORIZED),
+    }
+}
+
+// Raw probe errors, newest first. Optional `rpc` (nickname) and `limit` params.
+pub async fn get_errors(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<ErrorRecord>>, StatusCode> {
+    require_admin(&state, &headers)?;
+
+    let prefix = match params.get("rpc") {
+        Some(nickname) => format!("{}{}:", ERRORS_PREFIX, nickname),
+        None => ERRORS_PREFIX.to_string(),
+    };
+    let limit = params
+        .get("limit")
+        .and_then(|l| l.parse::<usize>().ok())
+        .unwrap_or(100);
+
+    let mut records: Vec<ErrorRecord> = scan_prefix::<ErrorRecord>(&state.db, &prefix)
+        .into_iter()
+        .map(|(_, record)| record)
+        .collect();
+    records.sort_by(|a, b| b.timestamp_ms.cmp(&a.timestamp_ms));
+    records.truncate(limit);
+
+    Ok(Json(records))
+}
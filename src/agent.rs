@@ -0,0 +1,69 @@
+use axum::{extract::State, http::HeaderMap, http::StatusCode, Json};
+use std::time::Duration;
+
+use crate::auth::bearer_matches;
+use crate::config::AgentConfig;
+use crate::ingest::validate_sample;
+use crate::models::{AgentBatch, RpcEndpoint};
+use crate::rpc::{log_sample, probe_round, store_response};
+use crate::state::AppState;
+
+// Agent mode: run the prober only and ship each round to the aggregator
+pub async fn run_agent(
+    agent: AgentConfig,
+    endpoints: Vec<RpcEndpoint>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()?;
+    let url = format!("{}/api/agent/samples", agent.aggregator_url.trim_end_matches('/'));
+
+    log_info!("🛰️ Agent mode: shipping samples for region {} to {}", agent.region, url);
+
+    loop {
+        let samples = probe_round(&endpoints).await;
+        samples.iter().for_each(log_sample);
+
+        let batch = AgentBatch {
+            region: agent.region.clone(),
+            samples,
+        };
+
+        match client.post(&url).bearer_auth(&agent.token).json(&batch).send().await {
+            Ok(response) if response.status().is_success() => {}
+            Ok(response) => log_error!("Aggregator rejected batch: HTTP {}", response.status()),
+            Err(e) => log_error!("Error shipping samples to aggregator: {}", e),
+        }
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(2000)).await;
+    }
+}
+
+// Aggregator side: accept a batch from an agent and tag it with the agent's region
+pub async fn receive_agent_samples(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(batch): Json<AgentBatch>,
+) -> StatusCode {
+    let token = match &state.config.aggregator {
+        Some(aggregator) => &aggregator.token,
+        None => return StatusCode::NOT_FOUND,
+    };
+    if !bearer_matches(&headers, token) {
+        return StatusCode::UNAUTHORIZED;
+    }
+
+    for mut sample in batch.samples {
+        sample.region = Some(batch.region.clone());
+        if let Err(e) = validate_sample(&sample, &state.config.retention) {
+            log_error!("Rejected agent sample: {}", e);
+            return StatusCode::BAD_REQUEST;
+        }
+        if let Err(e) = store_response(&state.db, &sample) {
+            log_error!("Error storing agent sample: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+    }
+
+    StatusCode::NO_CONTENT
+}
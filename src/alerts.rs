@@ -0,0 +1,366 @@
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::access::{allowed, resolve_scope};
+use crate::check::sample_errors;
+use crate::config::{AlertRuleConfig, AlertsConfig};
+use crate::models::RPCResponse;
+use crate::state::AppState;
+use crate::storage::{scan_prefix, Store, ALERTS_PREFIX};
+use crate::tags::Tags;
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertMetric {
+    LatencyMs,
+    /// Slots behind the highest slot seen in the same poll round
+    SlotLag,
+    /// 1 for a failed or unhealthy sample, 0 otherwise
+    Error,
+    /// 1 for a rate-limited sample, 0 otherwise
+    Throttled,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    #[serde(rename = ">")]
+    Above,
+    #[serde(rename = ">=")]
+    AtLeast,
+    #[serde(rename = "<")]
+    Below,
+    #[serde(rename = "<=")]
+    AtMost,
+}
+
+impl Comparison {
+    fn holds(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparison::Above => value > threshold,
+            Comparison::AtLeast => value >= threshold,
+            Comparison::Below => value < threshold,
+            Comparison::AtMost => value <= threshold,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertState {
+    #[default]
+    Firing,
+    Resolved,
+}
+
+/// A rule starting or ceasing to breach on one endpoint
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AlertEvent {
+    /// Unix milliseconds; records from before the switch hold float seconds under "timestamp"
+    #[serde(alias = "timestamp", deserialize_with = "crate::timestamps::deserialize_millis")]
+    pub timestamp_ms: i64,
+    pub rule: String,
+    pub nickname: String,
+    #[serde(default)]
+    pub state: AlertState,
+    /// When the breach started, in Unix milliseconds; set on resolution events
+    #[serde(default, alias = "opened_at", deserialize_with = "crate::timestamps::deserialize_millis_opt")]
+    pub opened_at_ms: Option<i64>,
+    pub metric: AlertMetric,
+    pub comparison: Comparison,
+    pub threshold: f64,
+    /// The metric's value in the latest sample
+    pub value: f64,
+    pub tags: Tags,
+}
+
+// What the rules need from one sample, captured when the poll round lands
+#[derive(Debug, Clone)]
+struct AlertSample {
+    timestamp: f64,
+    nickname: String,
+    tags: Tags,
+    latency_ms: f64,
+    slot_lag: f64,
+    error: bool,
+    throttled: bool,
+}
+
+impl AlertSample {
+    fn value(&self, metric: AlertMetric) -> f64 {
+        match metric {
+            AlertMetric::LatencyMs => self.latency_ms,
+            AlertMetric::SlotLag => self.slot_lag,
+            AlertMetric::Error => self.error as u8 as f64,
+            AlertMetric::Throttled => self.throttled as u8 as f64,
+        }
+    }
+}
+
+// Samples older than this mean the poller has stopped producing them for the endpoint
+const STALE_SECS: f64 = 60.0;
+
+struct Window {
+    keep_secs: f64,
+    samples: HashMap<String, VecDeque<AlertSample>>,
+}
+
+// None while no rules are configured, so nothing accumulates
+static WINDOW: Lazy<Mutex<Option<Window>>> = Lazy::new(|| Mutex::new(None));
+
+// Buffer one poll round for the evaluator
+pub fn record_round(samples: &[RPCResponse]) {
+    let mut guard = WINDOW.lock().unwrap();
+    let window = match guard.as_mut() {
+        Some(window) => window,
+        None => return,
+    };
+    let highest_slot = samples.iter().map(|s| s.slot).max().unwrap_or(0);
+
+    for sample in samples {
+        let series = window.samples.entry(sample.label()).or_default();
+        series.push_back(AlertSample {
+            timestamp: sample.timestamp_secs(),
+            nickname: sample.nickname.clone(),
+            tags: sample.tags.clone(),
+            latency_ms: sample.latency_ms as f64,
+            slot_lag: sample.slot_lag(highest_slot) as f64,
+            error: !sample_errors(sample).is_empty(),
+            throttled: sample.throttled,
+        });
+        while series
+            .front()
+            .map_or(false, |oldest| oldest.timestamp < sample.timestamp_secs() - window.keep_secs)
+        {
+            series.pop_front();
+        }
+    }
+}
+
+/// A breach that has fired and not yet cleared
+#[derive(Debug, Serialize, Clone)]
+pub struct OpenAlert {
+    pub rule: String,
+    pub nickname: String,
+    pub since: f64,
+    pub value: f64,
+    /// Opened during the rule's cooldown, so no notification went out
+    pub suppressed: bool,
+    #[serde(skip)]
+    tags: Tags,
+}
+
+// Open breaches keyed by (rule, series label), and when each pair last sent a firing notification
+static OPEN: Lazy<Mutex<HashMap<(String, String), OpenAlert>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static LAST_FIRED: Lazy<Mutex<HashMap<(String, String), f64>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// A rule is breached on an endpoint when every sample in the `duration_secs` leading up to
+// its latest one meets the condition. Returns None when there is nothing to judge by: the
+// rule doesn't select the endpoint, the buffer doesn't yet cover the whole duration, or the
+// series stopped receiving samples.
+fn evaluate<'a>(rule: &AlertRuleConfig, series: &'a VecDeque<AlertSample>, now: f64) -> Option<(bool, &'a AlertSample)> {
+    let latest = series.back()?;
+    if latest.timestamp < now - STALE_SECS || !rule.selects(&latest.nickname, &latest.tags) {
+        return None;
+    }
+    let since = latest.timestamp - rule.duration_secs() as f64;
+    if series.front()?.timestamp > since {
+        return None;
+    }
+    let breached = series
+        .iter()
+        .filter(|sample| sample.timestamp >= since)
+        .all(|sample| rule.comparison.holds(sample.value(rule.metric), rule.threshold));
+    Some((breached, latest))
+}
+
+// Advance the open/closed state of one rule on one series. Only the edges produce events:
+// a new breach fires once (unless the same pair fired within the cooldown, in which case it
+// stays open silently), and a cleared breach that did notify emits a resolution.
+fn step(rule: &AlertRuleConfig, label: &str, series: &VecDeque<AlertSample>, now: f64) -> Option<AlertEvent> {
+    let (breached, latest) = evaluate(rule, series, now)?;
+    let key = (rule.name.clone(), label.to_string());
+    let mut open = OPEN.lock().unwrap();
+
+    let event = |state: AlertState, opened_at: Option<f64>| AlertEvent {
+        timestamp_ms: (now * 1000.0) as i64,
+        rule: rule.name.clone(),
+        nickname: latest.nickname.clone(),
+        state,
+        opened_at_ms: opened_at.map(|since| (since * 1000.0) as i64),
+        metric: rule.metric,
+        comparison: rule.comparison,
+        threshold: rule.threshold,
+        value: latest.value(rule.metric),
+        tags: latest.tags.clone(),
+    };
+
+    match (breached, open.contains_key(&key)) {
+        (true, true) => {
+            if let Some(alert) = open.get_mut(&key) {
+                alert.value = latest.value(rule.metric);
+            }
+            None
+        }
+        (true, false) => {
+            let mut last_fired = LAST_FIRED.lock().unwrap();
+            let suppressed = last_fired
+                .get(&key)
+                .map_or(false, |fired| now - fired < rule.cooldown_secs() as f64);
+            if !suppressed {
+                last_fired.insert(key.clone(), now);
+            }
+            open.insert(key, OpenAlert {
+                rule: rule.name.clone(),
+                nickname: latest.nickname.clone(),
+                since: now,
+                value: latest.value(rule.metric),
+                suppressed,
+                tags: latest.tags.clone(),
+            });
+            (!suppressed).then(|| event(AlertState::Firing, None))
+        }
+        (false, true) => {
+            let alert = open.remove(&key)?;
+            (!alert.suppressed).then(|| event(AlertState::Resolved, Some(alert.since)))
+        }
+        (false, false) => None,
+    }
+}
+
+fn event_key(event: &AlertEvent) -> String {
+    format!("{}{}:{}:{}", ALERTS_PREFIX, event.rule, event.nickname, event.timestamp_ms)
+}
+
+async fn deliver(event: &AlertEvent, db: &Store, webhook_url: Option<&str>) {
+    match event.state {
+        AlertState::Firing => log_info!("🚨 [{}] {} firing: {:?} = {} (threshold {})", event.nickname, event.rule, event.metric, event.value, event.threshold),
+        AlertState::Resolved => log_info!("✅ [{}] {} resolved: {:?} = {}", event.nickname, event.rule, event.metric, event.value),
+    }
+    match serde_json::to_vec(event) {
+        Ok(body) => {
+            if let Err(e) = db.put(event_key(event).as_bytes(), body) {
+                log_error!("Error storing alert event: {}", e);
+            }
+        }
+        Err(e) => log_error!("Error encoding alert event: {}", e),
+    }
+
+    if let Some(url) = webhook_url {
+        match reqwest::Client::new().post(url).json(event).send().await {
+            Ok(response) if response.status().is_success() => {}
+            Ok(response) => log_error!("Alert webhook returned HTTP {}", response.status()),
+            Err(e) => log_error!("Error posting alert: {}", e),
+        }
+    }
+}
+
+// Start buffering samples and evaluate every rule against them on a fixed interval
+pub fn spawn_alerts(config: AlertsConfig, db: Arc<Store>) {
+    let rules: Vec<AlertRuleConfig> = config
+        .resolved_rules()
+        .into_iter()
+        .filter(|rule| {
+            let valid = !rule.name.is_empty() && !rule.name.contains(':');
+            if !valid {
+                log_error!("Skipping alert rule {:?}: names must be non-empty and must not contain ':'", rule.name);
+            }
+            valid
+        })
+        .collect();
+    if rules.is_empty() {
+        return;
+    }
+
+    // Keep a little more than the longest duration so the oldest sample can vouch for coverage
+    let keep_secs = rules.iter().map(|rule| rule.duration_secs()).max().unwrap_or(0) as f64 + 30.0;
+    *WINDOW.lock().unwrap() = Some(Window {
+        keep_secs,
+        samples: HashMap::new(),
+    });
+
+    let interval = Duration::from_secs(config.interval_secs());
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+
+            let now = Utc::now().timestamp_millis() as f64 / 1000.0;
+            let events: Vec<AlertEvent> = {
+                let guard = WINDOW.lock().unwrap();
+                let window = match guard.as_ref() {
+                    Some(window) => window,
+                    None => continue,
+                };
+                rules
+                    .iter()
+                    .flat_map(|rule| {
+                        window
+                            .samples
+                            .iter()
+                            .filter_map(move |(label, series)| step(rule, label, series, now))
+                    })
+                    .collect()
+            };
+
+            for event in &events {
+                deliver(event, &db, config.webhook_url.as_deref()).await;
+            }
+        }
+    });
+}
+
+// Recent alert events, newest first. Optional `rule`, `rpc` (nickname; requires `rule`) and `limit` params.
+pub async fn get_alerts(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<AlertEvent>>, StatusCode> {
+    let scope = resolve_scope(&state, &headers)?;
+
+    let prefix = match (params.get("rule"), params.get("rpc")) {
+        (Some(rule), Some(nickname)) => format!("{}{}:{}:", ALERTS_PREFIX, rule, nickname),
+        (Some(rule), None) => format!("{}{}:", ALERTS_PREFIX, rule),
+        (None, _) => ALERTS_PREFIX.to_string(),
+    };
+    let limit = params
+        .get("limit")
+        .and_then(|l| l.parse::<usize>().ok())
+        .unwrap_or(100);
+
+    let mut events: Vec<AlertEvent> = scan_prefix::<AlertEvent>(&state.db, &prefix)
+        .into_iter()
+        .map(|(_, event)| event)
+        .filter(|event| allowed(&scope, &event.nickname, &event.tags))
+        .collect();
+    events.sort_by(|a, b| b.timestamp_ms.cmp(&a.timestamp_ms));
+    events.truncate(limit);
+
+    Ok(Json(events))
+}
+
+// Breaches currently open, oldest first, including those held silent by a cooldown
+pub async fn get_open_alerts(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<OpenAlert>>, StatusCode> {
+    let scope = resolve_scope(&state, &headers)?;
+
+    let mut alerts: Vec<OpenAlert> = OPEN
+        .lock()
+        .unwrap()
+        .values()
+        .filter(|alert| allowed(&scope, &alert.nickname, &alert.tags))
+        .cloned()
+        .collect();
+    alerts.sort_by(|a, b| a.since.partial_cmp(&b.since).unwrap_or(std::cmp::Ordering::Equal));
+
+    Ok(Json(alerts))
+}
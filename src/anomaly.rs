@@ -0,0 +1,154 @@
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::access::{allowed, resolve_scope};
+use crate::check::sample_errors;
+use crate::config::AnomalyConfig;
+use crate::models::RPCResponse;
+use crate::state::AppState;
+use crate::storage::{scan_prefix, Store, ANOMALIES_PREFIX};
+use crate::tags::Tags;
+
+/// A sample whose latency strayed too far from its endpoint's baseline
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AnomalyEvent {
+    /// Unix milliseconds; records from before the switch hold float seconds under "timestamp"
+    #[serde(alias = "timestamp", deserialize_with = "crate::timestamps::deserialize_millis")]
+    pub timestamp_ms: i64,
+    pub nickname: String,
+    /// Probe region for samples shipped by a remote agent
+    pub region: Option<String>,
+    pub latency_ms: u128,
+    /// Baseline mean and standard deviation just before this sample
+    pub baseline_ms: f64,
+    pub stddev_ms: f64,
+    pub z_score: f64,
+    pub tags: Tags,
+}
+
+// Exponentially weighted mean and variance of one series' latency
+#[derive(Debug, Default)]
+struct Baseline {
+    samples: u32,
+    mean: f64,
+    variance: f64,
+}
+
+impl Baseline {
+    fn update(&mut self, value: f64, alpha: f64) {
+        if self.samples == 0 {
+            self.mean = value;
+        } else {
+            let diff = value - self.mean;
+            let increment = alpha * diff;
+            self.mean += increment;
+            self.variance = (1.0 - alpha) * (self.variance + diff * increment);
+        }
+        self.samples = self.samples.saturating_add(1);
+    }
+}
+
+struct Detector {
+    config: AnomalyConfig,
+    baselines: HashMap<String, Baseline>,
+}
+
+// None while detection is disabled
+static DETECTOR: Lazy<Mutex<Option<Detector>>> = Lazy::new(|| Mutex::new(None));
+
+pub fn enable(config: AnomalyConfig) {
+    *DETECTOR.lock().unwrap() = Some(Detector {
+        config,
+        baselines: HashMap::new(),
+    });
+}
+
+fn anomaly_key(event: &AnomalyEvent) -> String {
+    format!("{}{}:{}", ANOMALIES_PREFIX, event.nickname, event.timestamp_ms)
+}
+
+// Score each successful sample against its series' baseline before folding it in. Failed
+// samples are left out: their latency is a timeout, not a measurement.
+pub fn record_round(db: &Store, samples: &[RPCResponse]) {
+    let events: Vec<AnomalyEvent> = {
+        let mut guard = DETECTOR.lock().unwrap();
+        let detector = match guard.as_mut() {
+            Some(detector) => detector,
+            None => return,
+        };
+        let config = &detector.config;
+
+        samples
+            .iter()
+            .filter(|sample| sample_errors(sample).is_empty())
+            .filter_map(|sample| {
+                let baseline = detector.baselines.entry(sample.label()).or_default();
+                let latency = sample.latency_ms as f64;
+                let stddev = baseline.variance.sqrt().max(config.min_stddev_ms());
+                let z_score = (latency - baseline.mean) / stddev;
+                let event = (baseline.samples >= config.warmup_samples() && z_score.abs() >= config.z_score())
+                    .then(|| AnomalyEvent {
+                        timestamp_ms: sample.timestamp_ms,
+                        nickname: sample.nickname.clone(),
+                        region: sample.region.clone(),
+                        latency_ms: sample.latency_ms,
+                        baseline_ms: baseline.mean,
+                        stddev_ms: stddev,
+                        z_score,
+                        tags: sample.tags.clone(),
+                    });
+                baseline.update(latency, config.alpha());
+                event
+            })
+            .collect()
+    };
+
+    for event in events {
+        let label = match &event.region {
+            Some(region) => format!("{}@{}", event.nickname, region),
+            None => event.nickname.clone(),
+        };
+        log_info!("📈 [{}] Latency anomaly: {}ms vs baseline {:.1}ms (z = {:.1})", label, event.latency_ms, event.baseline_ms, event.z_score);
+        let result = serde_json::to_vec(&event)
+            .map_err(|e| e.to_string())
+            .and_then(|body| db.put(anomaly_key(&event).as_bytes(), body).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            log_error!("Error storing anomaly: {}", e);
+        }
+    }
+}
+
+// Recent latency anomalies, newest first. Optional `rpc` (nickname) and `limit` params.
+pub async fn get_anomalies(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<AnomalyEvent>>, StatusCode> {
+    let scope = resolve_scope(&state, &headers)?;
+
+    let prefix = match params.get("rpc") {
+        Some(nickname) => format!("{}{}:", ANOMALIES_PREFIX, nickname),
+        None => ANOMALIES_PREFIX.to_string(),
+    };
+    let limit = params
+        .get("limit")
+        .and_then(|l| l.parse::<usize>().ok())
+        .unwrap_or(100);
+
+    let mut events: Vec<AnomalyEvent> = scan_prefix::<AnomalyEvent>(&state.db, &prefix)
+        .into_iter()
+        .map(|(_, event)| event)
+        .filter(|event| allowed(&scope, &event.nickname, &event.tags))
+        .collect();
+    events.sort_by(|a, b| b.timestamp_ms.cmp(&a.timestamp_ms));
+    events.truncate(limit);
+
+    Ok(Json(events))
+}
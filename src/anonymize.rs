@@ -0,0 +1,247 @@
+use axum::{
+    body::{boxed, Full},
+    extract::State,
+    http::{header, uri::PathAndQuery, HeaderMap, Request, StatusCode, Uri},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use once_cell::sync::Lazy;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
+use rocksdb::{Direction, IteratorMode};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+use crate::auth::bearer_matches;
+use crate::state::AppState;
+use crate::storage::Store;
+
+// Fields that would give a provider away even with its name replaced: its URLs, the IP that
+// served a sample (`remote_ip`), the socket address of network and TPU probes (`address`) and
+// the external probe that pushed an ingested sample (`source`)
+const REVEALING_FIELDS: [&str; 6] = ["rpc_url", "url", "ws_url", "remote_ip", "address", "source"];
+
+// Stands in for a real nickname in requests, so guessing names can't confirm them, and for
+// names that have no label in responses
+const HIDDEN: &str = "~hidden";
+
+// Nicknames of stored samples, including those with no configured endpoint (ingested samples,
+// discovered nodes); the latter are shown to anonymized callers as HIDDEN
+static NICKNAMES: Lazy<Mutex<BTreeSet<String>>> = Lazy::new(|| Mutex::new(BTreeSet::new()));
+
+/// Remember the nickname of a stored sample
+pub fn note_nickname(nickname: &str) {
+    let mut nicknames = NICKNAMES.lock().unwrap();
+    if !nicknames.contains(nickname) {
+        nicknames.insert(nickname.to_string());
+    }
+}
+
+/// Collect the nicknames of the samples already stored, seeking once per label
+pub fn load(db: &Store) {
+    // Internal keys all start with '!', so samples begin right after them
+    let mut position = vec![b'!' + 1];
+    loop {
+        let key = match db.iterator(IteratorMode::From(&position, Direction::Forward)).next() {
+            Some(Ok((key, _))) => key,
+            _ => break,
+        };
+        let label = &key[..key.iter().position(|b| *b == b':').unwrap_or(key.len())];
+        let label = String::from_utf8_lossy(label);
+        note_nickname(label.split('@').next().unwrap_or_default());
+        // ';' sorts right after ':', so this skips the rest of the label's samples
+        position = format!("{};", label).into_bytes();
+    }
+}
+
+// "A".."Z", then "AA", "AB", ...
+fn letters(mut index: usize) -> String {
+    let mut letters = Vec::new();
+    loop {
+        letters.push((b'A' + (index % 26) as u8) as char);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    letters.iter().rev().collect()
+}
+
+// Nickname and anonymous label of each endpoint, lettered in config order so labels stay
+// the same across restarts as long as endpoints are only appended
+fn labels(state: &AppState) -> Vec<(String, String)> {
+    state
+        .config
+        .rpc
+        .endpoints
+        .iter()
+        .enumerate()
+        .map(|(index, endpoint)| (endpoint.nickname.clone(), format!("Provider {}", letters(index))))
+        .collect()
+}
+
+// `labels` plus every other known nickname mapped to HIDDEN, for what goes out to callers
+fn outgoing(state: &AppState) -> Vec<(String, String)> {
+    let mut pairs = labels(state);
+    let unlabeled: Vec<String> = NICKNAMES
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|nickname| !pairs.iter().any(|(configured, _)| configured == *nickname))
+        .cloned()
+        .collect();
+    pairs.extend(unlabeled.into_iter().map(|nickname| (nickname, HIDDEN.to_string())));
+    pairs
+}
+
+/// Whether this caller only gets anonymous labels: anonymization is on and they are not the admin
+pub fn anonymized(state: &AppState, headers: &HeaderMap) -> bool {
+    state.config.access.anonymize()
+        && !state
+            .config
+            .admin
+            .as_ref()
+            .map_or(false, |admin| bearer_matches(headers, &admin.token))
+}
+
+/// The name to show this caller for an endpoint
+pub fn public_name(state: &AppState, headers: &HeaderMap, nickname: &str) -> String {
+    if !anonymized(state, headers) {
+        return nickname.to_string();
+    }
+    labels(state)
+        .into_iter()
+        .find(|(name, _)| name == nickname)
+        .map(|(_, label)| label)
+        .unwrap_or_else(|| HIDDEN.to_string())
+}
+
+// Swap the endpoint part of a sample label ("<name>" or "<name>@<region>"), from nickname to
+// label or back
+fn swap(value: &str, pairs: &[(String, String)], to_label: bool) -> Option<String> {
+    let (base, region) = match value.split_once('@') {
+        Some((base, region)) => (base, Some(region)),
+        None => (value, None),
+    };
+    let replacement = pairs.iter().find_map(|(nickname, label)| {
+        let (from, to) = if to_label { (nickname, label) } else { (label, nickname) };
+        (from == base).then(|| to.clone())
+    })?;
+    Some(match region {
+        Some(region) => format!("{}@{}", replacement, region),
+        None => replacement,
+    })
+}
+
+// An incoming label becomes the real nickname and an incoming real nickname, labeled or not,
+// matches nothing
+fn translate_input(value: &str, pairs: &[(String, String)], outgoing: &[(String, String)]) -> Option<String> {
+    match swap(value, pairs, false) {
+        Some(nickname) => Some(nickname),
+        None if swap(value, outgoing, true).is_some() => Some(HIDDEN.to_string()),
+        None => None,
+    }
+}
+
+/// The configured nickname a caller means by `name`: a label for anonymized callers, the
+/// nickname itself for everyone else
+pub fn real_nickname(state: &AppState, headers: &HeaderMap, name: &str) -> String {
+    if !anonymized(state, headers) {
+        return name.to_string();
+    }
+    translate_input(name, &labels(state), &outgoing(state)).unwrap_or_else(|| name.to_string())
+}
+
+// Query params are read by handlers after routing, so labels in them can still be swapped
+// here; path params are resolved by the handlers through `real_nickname`
+fn translate_query(uri: &Uri, pairs: &[(String, String)], outgoing: &[(String, String)]) -> Option<Uri> {
+    let query = match uri.query() {
+        Some(query) => query,
+        None => return Some(uri.clone()),
+    };
+    let query = query
+        .split('&')
+        .map(|pair| {
+            let (key, value) = match pair.split_once('=') {
+                Some(split) => split,
+                None => return pair.to_string(),
+            };
+            let decoded = percent_decode_str(&value.replace('+', " ")).decode_utf8_lossy().to_string();
+            match translate_input(&decoded, pairs, outgoing) {
+                Some(translated) => format!("{}={}", key, utf8_percent_encode(&translated, NON_ALPHANUMERIC)),
+                None => pair.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+    let path_and_query = format!("{}?{}", uri.path(), query);
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = Some(path_and_query.parse::<PathAndQuery>().ok()?);
+    Uri::from_parts(parts).ok()
+}
+
+fn anonymize_value(value: &mut Value, pairs: &[(String, String)]) {
+    match value {
+        Value::Object(map) => {
+            map.remove("tags");
+            let entries = std::mem::take(map);
+            for (key, mut child) in entries {
+                if REVEALING_FIELDS.contains(&key.as_str()) && child.is_string() {
+                    child = Value::String(String::new());
+                }
+                anonymize_value(&mut child, pairs);
+                map.insert(swap(&key, pairs, true).unwrap_or(key), child);
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| anonymize_value(item, pairs)),
+        Value::String(text) => {
+            if let Some(label) = swap(text, pairs, true) {
+                *text = label;
+            }
+        }
+        _ => {}
+    }
+}
+
+// With `[access] anonymize = true`, everyone but the admin sees "Provider A", "Provider B", ...
+// instead of endpoint nicknames: labels in query params are mapped to the real endpoints, and
+// nicknames, URLs, IP addresses, sources and tags are taken out of JSON responses. Nicknames
+// without a configured endpoint are shown as "~hidden".
+pub async fn anonymize_responses<B>(State(state): State<AppState>, mut request: Request<B>, next: Next<B>) -> Response {
+    if !anonymized(&state, request.headers()) {
+        return next.run(request).await;
+    }
+    let pairs = labels(&state);
+    let outgoing = outgoing(&state);
+    match translate_query(request.uri(), &pairs, &outgoing) {
+        Some(uri) => *request.uri_mut() = uri,
+        None => return StatusCode::BAD_REQUEST.into_response(),
+    }
+
+    let response = next.run(request).await;
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .map_or(false, |content_type| content_type.as_bytes().starts_with(b"application/json"));
+    if !is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match hyper::body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    let mut value: Value = match serde_json::from_slice(&bytes) {
+        Ok(value) => value,
+        // Never pass through a body that could not be checked for names
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    anonymize_value(&mut value, &outgoing);
+    let body = match serde_json::to_vec(&value) {
+        Ok(body) => body,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, boxed(Full::from(body)))
+}
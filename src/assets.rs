@@ -0,0 +1,23 @@
+use axum::{
+    extract::Path,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+// Dashboard assets compiled into the binary: (path, content type, body)
+const ASSETS: &[(&str, &str, &str)] = &[
+    ("index.html", "text/html; charset=utf-8", include_str!("static/index.html")),
+    ("dashboard.js", "application/javascript; charset=utf-8", include_str!("static/dashboard.js")),
+    ("darkMode.js", "application/javascript; charset=utf-8", include_str!("static/darkMode.js")),
+    ("styles.css", "text/css; charset=utf-8", include_str!("static/styles.css")),
+    ("logo.svg", "image/svg+xml", include_str!("static/logo.svg")),
+];
+
+// Serve /static/* straight from memory so nothing is written to the working directory
+pub async fn serve_asset(Path(path): Path<String>) -> Response {
+    let path = path.trim_start_matches('/');
+    match ASSETS.iter().find(|(name, _, _)| *name == path) {
+        Some((_, content_type, body)) => ([(header::CONTENT_TYPE, *content_type)], *body).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
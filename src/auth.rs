@@ -0,0 +1,21 @@
+use axum::http::{header::AUTHORIZATION, HeaderMap};
+
+/// Check an `Authorization: Bearer <token>` header against the expected token
+pub fn bearer_matches(headers: &HeaderMap, expected: &str) -> bool {
+    let provided = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match provided {
+        Some(token) => constant_time_eq(token.as_bytes(), expected.as_bytes()),
+        None => false,
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
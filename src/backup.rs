@@ -0,0 +1,102 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::Utc;
+use rocksdb::checkpoint::Checkpoint;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::admin::require_admin;
+use crate::state::AppState;
+use crate::storage::Store;
+
+#[derive(Debug, Serialize)]
+pub struct BackupInfo {
+    pub path: String,
+    pub created_at: i64,
+}
+
+// A RocksDB checkpoint is a consistent point-in-time copy of the live database. SST files
+// are hard-linked when the target is on the same filesystem, so this is cheap and safe to
+// run while the monitor keeps writing.
+pub fn create_checkpoint(db: &Store, backup_dir: &Path) -> Result<BackupInfo, Box<dyn std::error::Error>> {
+    let db = db.disk().ok_or("Backups need [storage] mode = \"disk\"")?;
+    fs::create_dir_all(backup_dir)?;
+    let now = Utc::now();
+    let path = backup_dir.join(format!("rpc_metrics-{}", now.format("%Y%m%dT%H%M%SZ")));
+    if path.exists() {
+        return Err(format!("{} already exists", path.display()).into());
+    }
+    Checkpoint::new(db)?.create_checkpoint(&path)?;
+    Ok(BackupInfo {
+        path: path.display().to_string(),
+        created_at: now.timestamp(),
+    })
+}
+
+// Replace the database at `db_path` with a checkpoint before it is opened. The current
+// database, if any, is moved aside rather than deleted.
+pub fn restore(from: &Path, db_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    if !from.join("CURRENT").is_file() {
+        return Err(format!("{} does not look like a RocksDB checkpoint", from.display()).into());
+    }
+    if db_path.exists() {
+        let mut aside = db_path.as_os_str().to_owned();
+        aside.push(format!(".pre-restore-{}", Utc::now().format("%Y%m%dT%H%M%SZ")));
+        let aside = PathBuf::from(aside);
+        fs::rename(db_path, &aside)?;
+        log_info!("📦 Moved existing database to {}", aside.display());
+    }
+    copy_dir(from, db_path)?;
+    log_info!("📦 Restored database from {}", from.display());
+    Ok(())
+}
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+// Take a checkpoint of the live database into `[storage] backup_dir`
+pub async fn post_backup(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<BackupInfo>, StatusCode> {
+    require_admin(&state, &headers)?;
+    if state.config.storage.in_memory() {
+        log_error!("Backups need [storage] mode = \"disk\"");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let db = Arc::clone(&state.db);
+    let backup_dir = state.config.storage.backup_dir();
+    let result = tokio::task::spawn_blocking(move || {
+        create_checkpoint(&db, &backup_dir).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    match result {
+        Ok(info) => {
+            log_info!("📦 Database checkpoint written to {}", info.path);
+            Ok(Json(info))
+        }
+        Err(e) => {
+            log_error!("Error creating database checkpoint: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+use crate::anonymize;
+use crate::endpoints::find_endpoint;
+use crate::histograms;
+use crate::state::AppState;
+use crate::status::{self, EndpointState, STATUS_P95_MINUTES};
+
+// Badges may be cached this long by browsers and image proxies (e.g. GitHub's camo)
+const BADGE_MAX_AGE_SECS: u64 = 60;
+
+// Rough Verdana 11px advance width; close enough to size the two halves of the badge
+fn text_width(text: &str) -> usize {
+    text.chars().count() * 7 + 10
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Flat, shields.io-style badge: grey label on the left, colored message on the right
+fn render(label: &str, message: &str, color: &str) -> String {
+    let (label, message) = (escape(label), escape(message));
+    let label_width = text_width(&label);
+    let message_width = text_width(&message);
+    let width = label_width + message_width;
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {message}"><title>{label}: {message}</title><linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient><clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath><g clip-path="url(#r)"><rect width="{label_width}" height="20" fill="#555"/><rect x="{label_width}" width="{message_width}" height="20" fill="{color}"/><rect width="{width}" height="20" fill="url(#s)"/></g><g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11"><text x="{label_x}" y="14">{label}</text><text x="{message_x}" y="14">{message}</text></g></svg>"##,
+        width = width,
+        label = label,
+        message = message,
+        label_width = label_width,
+        message_width = message_width,
+        color = color,
+        label_x = label_width / 2,
+        message_x = label_width + message_width / 2,
+    )
+}
+
+// Current state and p95 latency of one endpoint as an SVG badge, served at
+// `/badge/<nickname>.svg` for embedding in READMEs and wikis
+pub async fn get_badge(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(file): Path<String>,
+) -> Result<Response, StatusCode> {
+    let nickname = file.strip_suffix(".svg").ok_or(StatusCode::NOT_FOUND)?;
+    let endpoint = find_endpoint(&state, &headers, nickname)?;
+
+    let (message, color) = match status::snapshot().remove(&endpoint.nickname) {
+        Some(status) => {
+            let (name, color) = match status.state {
+                EndpointState::Healthy => ("healthy", "#4c1"),
+                EndpointState::Degraded => ("degraded", "#dfb317"),
+                EndpointState::Down => ("down", "#e05d44"),
+                EndpointState::Paused => ("paused", "#9f9f9f"),
+            };
+            let latency = histograms::recent_quantile(&state.db, &endpoint.nickname, STATUS_P95_MINUTES, 0.95)
+                .filter(|_| status.state != EndpointState::Paused);
+            match latency {
+                Some(latency) => (format!("{} | {}ms", name, latency), color),
+                None => (name.to_string(), color),
+            }
+        }
+        None => ("unknown".to_string(), "#9f9f9f"),
+    };
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "image/svg+xml".to_string()),
+            (header::CACHE_CONTROL, format!("max-age={}", BADGE_MAX_AGE_SECS)),
+        ],
+        render(&anonymize::public_name(&state, &headers, &endpoint.nickname), &message, color),
+    )
+        .into_response())
+}
@@ -0,0 +1,61 @@
+use futures::{stream, StreamExt};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Instant;
+
+use crate::models::RpcEndpoint;
+use crate::rpc::{time_method, CallError};
+use crate::stats::LatencySummary;
+
+pub struct BenchOptions {
+    pub method: String,
+    pub params: Option<Value>,
+    pub requests: usize,
+    pub concurrency: usize,
+}
+
+// Fire a burst of identical requests at one endpoint and summarize the latencies
+pub async fn run_bench(endpoint: &RpcEndpoint, options: BenchOptions) -> Result<(), Box<dyn std::error::Error>> {
+    println!(
+        "Benchmarking {} on {}: {} requests, concurrency {}",
+        options.method, endpoint.nickname, options.requests, options.concurrency
+    );
+
+    let started = Instant::now();
+    let results: Vec<Result<u128, CallError>> = stream::iter(0..options.requests)
+        .map(|_| time_method(endpoint, &options.method, options.params.clone()))
+        .buffer_unordered(options.concurrency.max(1))
+        .collect()
+        .await;
+    let elapsed = started.elapsed();
+
+    let mut latencies = Vec::new();
+    let mut errors: HashMap<String, usize> = HashMap::new();
+    for result in results {
+        match result {
+            Ok(latency) => latencies.push(latency),
+            Err(e) => *errors.entry(e.summary).or_insert(0) += 1,
+        }
+    }
+
+    let summary = LatencySummary::from_samples(&latencies);
+    println!(
+        "ok: {}  errors: {}  elapsed: {:.2}s  throughput: {:.1} req/s",
+        summary.count,
+        options.requests - summary.count,
+        elapsed.as_secs_f64(),
+        options.requests as f64 / elapsed.as_secs_f64().max(f64::EPSILON)
+    );
+    println!(
+        "min: {}ms  avg: {:.1}ms  p50: {}ms  p95: {}ms  p99: {}ms  max: {}ms",
+        summary.min, summary.avg, summary.p50, summary.p95, summary.p99, summary.max
+    );
+
+    let mut errors: Vec<_> = errors.into_iter().collect();
+    errors.sort_by(|a, b| b.1.cmp(&a.1));
+    for (error, count) in errors {
+        println!("  {:>5} x {}", count, error);
+    }
+
+    Ok(())
+}
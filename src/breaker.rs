@@ -0,0 +1,162 @@
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::BreakerConfig;
+use crate::models::{RPCResponse, RpcEndpoint};
+
+// Summary attached to the placeholder samples served while a breaker is open
+pub const CIRCUIT_OPEN: &str = "circuit open";
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    Closed,
+    /// Not probed until the open period ends
+    Open,
+    /// The open period ended; the next probe decides whether to close or reopen
+    HalfOpen,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BreakerStatus {
+    pub state: BreakerState,
+    pub consecutive_failures: u32,
+    /// Seconds until the next re-test while open
+    pub retest_in_secs: Option<u64>,
+    #[serde(skip)]
+    opened_at: Option<Instant>,
+    #[serde(skip)]
+    open_for: Duration,
+}
+
+struct Breakers {
+    config: BreakerConfig,
+    endpoints: HashMap<String, BreakerStatus>,
+}
+
+// None while the breaker is disabled, in which case every endpoint is always probed
+static BREAKERS: Lazy<Mutex<Option<Breakers>>> = Lazy::new(|| Mutex::new(None));
+
+pub fn enable(config: BreakerConfig) {
+    *BREAKERS.lock().unwrap() = Some(Breakers {
+        config,
+        endpoints: HashMap::new(),
+    });
+}
+
+/// Whether the endpoint should get a real probe this round. An open breaker whose period
+/// has passed moves to half-open and lets one probe through.
+pub fn allow(nickname: &str) -> bool {
+    let mut guard = BREAKERS.lock().unwrap();
+    let status = match guard.as_mut().and_then(|breakers| breakers.endpoints.get_mut(nickname)) {
+        Some(status) => status,
+        None => return true,
+    };
+    match (status.state, status.opened_at) {
+        (BreakerState::Open, Some(opened_at)) if opened_at.elapsed() < status.open_for => false,
+        (BreakerState::Open, _) => {
+            status.state = BreakerState::HalfOpen;
+            true
+        }
+        _ => true,
+    }
+}
+
+// A probe failed outright when it got neither chain data nor a timing measurement;
+// degraded answers (unhealthy node, HTTP/1.1 fallback) still count as the endpoint responding
+fn failed(sample: &RPCResponse) -> bool {
+    sample.slot == 0 && sample.protocol.is_none()
+}
+
+/// Feed a poll round's real samples into the breakers
+pub fn record_round(samples: &[RPCResponse]) {
+    let mut guard = BREAKERS.lock().unwrap();
+    let breakers = match guard.as_mut() {
+        Some(breakers) => breakers,
+        None => return,
+    };
+    let config = &breakers.config;
+
+    for sample in samples.iter().filter(|s| s.region.is_none() && s.error.as_deref() != Some(CIRCUIT_OPEN)) {
+        let status = breakers.endpoints.entry(sample.nickname.clone()).or_insert_with(|| BreakerStatus {
+            state: BreakerState::Closed,
+            consecutive_failures: 0,
+            retest_in_secs: None,
+            opened_at: None,
+            open_for: Duration::from_secs(config.open_secs()),
+        });
+
+        if !failed(sample) {
+            if status.state != BreakerState::Closed {
+                log_info!("[{}] Circuit closed", sample.nickname);
+            }
+            status.state = BreakerState::Closed;
+            status.consecutive_failures = 0;
+            status.opened_at = None;
+            status.open_for = Duration::from_secs(config.open_secs());
+            continue;
+        }
+
+        status.consecutive_failures += 1;
+        let reopen = status.state == BreakerState::HalfOpen;
+        if reopen || status.consecutive_failures >= config.failures() {
+            // Each failed re-test doubles the open period, up to the configured maximum
+            if reopen {
+                status.open_for = (status.open_for * 2).min(Duration::from_secs(config.max_open_secs()));
+            }
+            status.state = BreakerState::Open;
+            status.opened_at = Some(Instant::now());
+            log_error!(
+                "[{}] Circuit open after {} consecutive failures; re-testing in {}s",
+                sample.nickname,
+                status.consecutive_failures,
+                status.open_for.as_secs()
+            );
+        }
+    }
+}
+
+/// Current breaker state of an endpoint, if it has been probed since the breaker was enabled
+pub fn status(nickname: &str) -> Option<BreakerStatus> {
+    let guard = BREAKERS.lock().unwrap();
+    let mut status = guard.as_ref()?.endpoints.get(nickname)?.clone();
+    if status.state == BreakerState::Open {
+        status.retest_in_secs = status
+            .opened_at
+            .map(|opened_at| status.open_for.saturating_sub(opened_at.elapsed()).as_secs());
+    }
+    Some(status)
+}
+
+/// The sample recorded for an endpoint skipped because its breaker is open, so status
+/// tracking, alerts and the dashboard keep seeing it as down
+pub fn open_sample(endpoint: &RpcEndpoint) -> RPCResponse {
+    RPCResponse {
+        timestamp_ms: chrono::Utc::now().timestamp_millis(),
+        slot: 0,
+        blockhash: "Unavailable".to_string(),
+        latency_ms: 1,
+        rpc_url: endpoint.url.clone(),
+        nickname: endpoint.nickname.clone(),
+        protocol: None,
+        remote_ip: None,
+        region: None,
+        source: None,
+        error: Some(CIRCUIT_OPEN.to_string()),
+        resolution: None,
+        health: None,
+        block_height: None,
+        throttled: false,
+        retry_after_secs: None,
+        tags: endpoint.tags.clone(),
+        http2_latency_ms: None,
+        http1_latency_ms: None,
+        kind: endpoint.kind,
+        round: None,
+        sample_count: None,
+        throttled_count: None,
+    }
+}
@@ -0,0 +1,121 @@
+use serde::Serialize;
+
+use crate::models::{EndpointKind, HealthStatus, RPCResponse, RpcEndpoint};
+use crate::rpc::probe_round;
+
+/// Limits an endpoint must stay within for `check` to succeed
+pub struct CheckThresholds {
+    pub max_latency_ms: u128,
+    pub max_slot_lag: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CheckResult {
+    pub nickname: String,
+    pub latency_ms: u128,
+    pub slot: u64,
+    pub slot_lag: u64,
+    pub errors: Vec<String>,
+    pub ok: bool,
+}
+
+// The prober reports failures as placeholder values rather than errors
+pub fn sample_errors(sample: &RPCResponse) -> Vec<String> {
+    // Rollups keep no per-call detail; their `error` says how many of their samples failed
+    if sample.resolution.is_some() {
+        return sample.error.iter().cloned().collect();
+    }
+    let mut errors: Vec<String> = sample.error.iter().cloned().collect();
+    // Jito endpoints have no chain state to report
+    if sample.kind == EndpointKind::Rpc {
+        if sample.blockhash == "Unavailable" {
+            errors.push("blockhash unavailable".to_string());
+        }
+        if sample.slot == 0 {
+            errors.push("slot unavailable".to_string());
+        }
+    }
+    if sample.protocol.is_none() {
+        errors.push("timing probe failed".to_string());
+    }
+    if let Some(HealthStatus::Unhealthy { num_slots_behind, .. }) = &sample.health {
+        errors.push(match num_slots_behind {
+            Some(behind) => format!("node unhealthy ({} slots behind)", behind),
+            None => "node unhealthy".to_string(),
+        });
+    }
+    errors
+}
+
+pub fn evaluate(samples: &[RPCResponse], thresholds: &CheckThresholds) -> Vec<CheckResult> {
+    let highest_slot = samples.iter().map(|s| s.slot).max().unwrap_or(0);
+
+    let mut results: Vec<CheckResult> = samples
+        .iter()
+        .map(|sample| {
+            let mut errors = sample_errors(sample);
+            let slot_lag = sample.slot_lag(highest_slot);
+
+            if sample.latency_ms > thresholds.max_latency_ms {
+                errors.push(format!("latency above {}ms", thresholds.max_latency_ms));
+            }
+            if slot_lag > thresholds.max_slot_lag {
+                errors.push(format!("slot lag above {}", thresholds.max_slot_lag));
+            }
+
+            CheckResult {
+                nickname: sample.nickname.clone(),
+                latency_ms: sample.latency_ms,
+                slot: sample.slot,
+                slot_lag,
+                ok: errors.is_empty(),
+                errors,
+            }
+        })
+        .collect();
+
+    results.sort_by_key(|r| r.latency_ms);
+    results
+}
+
+fn print_table(results: &[CheckResult]) {
+    let width = results
+        .iter()
+        .map(|r| r.nickname.len())
+        .max()
+        .unwrap_or(0)
+        .max("ENDPOINT".len());
+
+    println!(
+        "{:<width$}  {:>10}  {:>12}  {:>6}  ERRORS",
+        "ENDPOINT", "LATENCY", "SLOT", "LAG"
+    );
+    for r in results {
+        println!(
+            "{:<width$}  {:>8}ms  {:>12}  {:>6}  {}",
+            r.nickname,
+            r.latency_ms,
+            r.slot,
+            r.slot_lag,
+            if r.errors.is_empty() { "-".to_string() } else { r.errors.join(", ") }
+        );
+    }
+}
+
+// One-shot probe of every endpoint; returns whether all endpoints passed
+pub async fn run_check(
+    endpoints: &[RpcEndpoint],
+    thresholds: CheckThresholds,
+    json: bool,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let samples = probe_round(endpoints).await;
+    let results = evaluate(&samples, &thresholds);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+    } else {
+        print_table(&results);
+    }
+
+    Ok(results.iter().all(|r| r.ok))
+}
@@ -0,0 +1,217 @@
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use crate::access::resolve_scope;
+use crate::check::sample_errors;
+use crate::config::ClusterEventsConfig;
+use crate::models::{EndpointKind, RPCResponse};
+use crate::state::AppState;
+use crate::storage::{scan_prefix, Store, CLUSTER_EVENTS_PREFIX};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ClusterEventKind {
+    /// Slots advanced slower than `max_slot_time_ms` on average over the window
+    SlowSlots,
+    /// More than `max_skip_pct` of the window's slots produced no block
+    SkippedSlots,
+}
+
+/// A period in which the cluster itself misbehaved, as seen by every endpoint at once
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClusterEvent {
+    pub kind: ClusterEventKind,
+    /// Unix milliseconds; records from before the switch hold float seconds under "started_at"
+    #[serde(alias = "started_at", deserialize_with = "crate::timestamps::deserialize_millis")]
+    pub started_at_ms: i64,
+    /// Empty while the event is ongoing
+    #[serde(default, alias = "ended_at", deserialize_with = "crate::timestamps::deserialize_millis_opt")]
+    pub ended_at_ms: Option<i64>,
+    pub start_slot: u64,
+    pub end_slot: Option<u64>,
+    /// Worst average slot time (ms) or skip rate (%) seen while the event was open
+    pub worst_value: f64,
+    pub threshold: f64,
+}
+
+// Highest healthy slot of one poll round, with the block height reported alongside it
+struct Observation {
+    timestamp: f64,
+    slot: u64,
+    block_height: Option<u64>,
+}
+
+struct Tracker {
+    config: ClusterEventsConfig,
+    observations: VecDeque<Observation>,
+    open: HashMap<ClusterEventKind, ClusterEvent>,
+}
+
+// None while cluster event detection is disabled
+static TRACKER: Lazy<Mutex<Option<Tracker>>> = Lazy::new(|| Mutex::new(None));
+
+pub fn enable(config: ClusterEventsConfig) {
+    *TRACKER.lock().unwrap() = Some(Tracker {
+        config,
+        observations: VecDeque::new(),
+        open: HashMap::new(),
+    });
+}
+
+// Events are keyed as "!cluster:<start_millis>:<kind>" and rewritten when they end
+fn event_key(event: &ClusterEvent) -> String {
+    let kind = serde_json::to_value(event.kind)
+        .ok()
+        .and_then(|kind| kind.as_str().map(str::to_string))
+        .unwrap_or_default();
+    format!("{}{}:{}", CLUSTER_EVENTS_PREFIX, event.started_at_ms, kind)
+}
+
+fn store_event(db: &Store, event: &ClusterEvent) {
+    let result = serde_json::to_vec(event)
+        .map_err(|e| e.to_string())
+        .and_then(|body| db.put(event_key(event).as_bytes(), body).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        log_error!("Error storing cluster event: {}", e);
+    }
+}
+
+// Follow the fleet's highest healthy slot from round to round. Over the configured window,
+// slow slot progression or a high share of slots without a block opens a cluster event,
+// which closes once the window looks normal again.
+pub fn record_round(db: &Store, samples: &[RPCResponse]) {
+    let mut guard = TRACKER.lock().unwrap();
+    let tracker = match guard.as_mut() {
+        Some(tracker) => tracker,
+        None => return,
+    };
+    let tip = samples
+        .iter()
+        .filter(|s| s.region.is_none() && s.kind == EndpointKind::Rpc && sample_errors(s).is_empty())
+        .max_by_key(|s| s.slot);
+    let tip = match tip {
+        Some(tip) => tip,
+        None => return,
+    };
+
+    tracker.observations.push_back(Observation {
+        timestamp: tip.timestamp_secs(),
+        slot: tip.slot,
+        block_height: tip.block_height,
+    });
+    let window_secs = tracker.config.window_secs() as f64;
+    while tracker
+        .observations
+        .front()
+        .map_or(false, |oldest| oldest.timestamp < tip.timestamp_secs() - window_secs)
+    {
+        tracker.observations.pop_front();
+    }
+
+    let (first, last) = match (tracker.observations.front(), tracker.observations.back()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return,
+    };
+    // Judge only a (nearly) full window so a short gap at startup doesn't look like a stall
+    let elapsed_ms = (last.timestamp - first.timestamp) * 1000.0;
+    if elapsed_ms < window_secs * 1000.0 * 0.8 {
+        return;
+    }
+    let slots = last.slot.saturating_sub(first.slot);
+    let slot_time_ms = elapsed_ms / slots.max(1) as f64;
+    let skip_pct = match (first.block_height, last.block_height) {
+        (Some(first_height), Some(last_height)) if slots > 0 => {
+            let blocks = last_height.saturating_sub(first_height).min(slots);
+            Some((slots - blocks) as f64 / slots as f64 * 100.0)
+        }
+        _ => None,
+    };
+
+    let config = &tracker.config;
+    let checks = [
+        (ClusterEventKind::SlowSlots, Some(slot_time_ms), config.max_slot_time_ms()),
+        (ClusterEventKind::SkippedSlots, skip_pct, config.max_skip_pct()),
+    ];
+    for (kind, value, threshold) in checks {
+        let value = match value {
+            Some(value) => value,
+            None => continue,
+        };
+        let breached = value > threshold;
+        match (breached, tracker.open.contains_key(&kind)) {
+            (true, false) => {
+                let event = ClusterEvent {
+                    kind,
+                    started_at_ms: tip.timestamp_ms,
+                    ended_at_ms: None,
+                    start_slot: tip.slot,
+                    end_slot: None,
+                    worst_value: value,
+                    threshold,
+                };
+                log_error!("🌐 Cluster event {:?} started at slot {} ({:.1} vs {:.1})", kind, tip.slot, value, threshold);
+                store_event(db, &event);
+                tracker.open.insert(kind, event);
+            }
+            (true, true) => {
+                if let Some(event) = tracker.open.get_mut(&kind) {
+                    if value > event.worst_value {
+                        event.worst_value = value;
+                        store_event(db, event);
+                    }
+                }
+            }
+            (false, true) => {
+                if let Some(mut event) = tracker.open.remove(&kind) {
+                    event.ended_at_ms = Some(tip.timestamp_ms);
+                    event.end_slot = Some(tip.slot);
+                    log_info!("🌐 Cluster event {:?} ended at slot {} (worst {:.1})", kind, tip.slot, event.worst_value);
+                    store_event(db, &event);
+                }
+            }
+            (false, false) => {}
+        }
+    }
+}
+
+// Recent cluster events, newest first. Optional `open` ("true" for ongoing ones only) and `limit` params.
+pub async fn get_cluster_events(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<ClusterEvent>>, StatusCode> {
+    // Cluster events concern no particular endpoint, so any valid key may read them
+    resolve_scope(&state, &headers)?;
+
+    let open_only = params.get("open").map(|open| open == "true").unwrap_or(false);
+    let limit = params
+        .get("limit")
+        .and_then(|l| l.parse::<usize>().ok())
+        .unwrap_or(100);
+
+    // Ongoing events come from memory; a stored one left open by a restart never ends
+    let mut events: Vec<ClusterEvent> = if open_only {
+        TRACKER
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|tracker| tracker.open.values().cloned().collect())
+            .unwrap_or_default()
+    } else {
+        scan_prefix::<ClusterEvent>(&state.db, CLUSTER_EVENTS_PREFIX)
+            .into_iter()
+            .map(|(_, event)| event)
+            .collect()
+    };
+    events.sort_by(|a, b| b.started_at_ms.cmp(&a.started_at_ms));
+    events.truncate(limit);
+
+    Ok(Json(events))
+}
@@ -0,0 +1,143 @@
+use std::time::{Duration, Instant};
+
+use crate::check::sample_errors;
+use crate::models::RpcEndpoint;
+use crate::rpc::probe_endpoint;
+use crate::stats::{percentile, LatencySummary};
+
+pub struct CompareOptions {
+    pub duration: Duration,
+    pub interval: Duration,
+}
+
+/// Parse durations like "90s", "5m" or "1h" (a bare number is seconds)
+pub fn parse_duration(input: &str) -> Result<Duration, String> {
+    let input = input.trim();
+    let (number, unit) = match input.find(|c: char| !c.is_ascii_digit()) {
+        Some(idx) => input.split_at(idx),
+        None => (input, "s"),
+    };
+    let value: u64 = number
+        .parse()
+        .map_err(|_| format!("Invalid duration: {}", input))?;
+
+    let secs = match unit {
+        "ms" => return Ok(Duration::from_millis(value)),
+        "s" => Some(value),
+        "m" => value.checked_mul(60),
+        "h" => value.checked_mul(3600),
+        _ => return Err(format!("Invalid duration unit in {}", input)),
+    };
+    secs.map(Duration::from_secs)
+        .ok_or_else(|| format!("Invalid duration: {}", input))
+}
+
+#[derive(Default)]
+struct SideStats {
+    latencies: Vec<u128>,
+    errors: usize,
+}
+
+fn print_side(endpoint: &RpcEndpoint, stats: &SideStats, rounds: usize) {
+    let summary = LatencySummary::from_samples(&stats.latencies);
+    println!(
+        "{:<20} avg {:>7.1}ms  p50 {:>5}ms  p95 {:>5}ms  p99 {:>5}ms  errors {:>5.1}%",
+        endpoint.nickname,
+        summary.avg,
+        summary.p50,
+        summary.p95,
+        summary.p99,
+        stats.errors as f64 * 100.0 / rounds.max(1) as f64
+    );
+}
+
+// Probe two endpoints side by side and print a statistical comparison
+pub async fn run_compare(a: &RpcEndpoint, b: &RpcEndpoint, options: CompareOptions) -> Result<(), Box<dyn std::error::Error>> {
+    println!(
+        "Comparing {} vs {} for {}s (every {}ms)...",
+        a.nickname,
+        b.nickname,
+        options.duration.as_secs(),
+        options.interval.as_millis()
+    );
+
+    let mut stats_a = SideStats::default();
+    let mut stats_b = SideStats::default();
+    let mut deltas: Vec<i128> = Vec::new();
+    let (mut a_ahead, mut b_ahead, mut tied) = (0usize, 0usize, 0usize);
+    let mut rounds = 0usize;
+
+    let started = Instant::now();
+    while started.elapsed() < options.duration {
+        let (sample_a, sample_b) = tokio::join!(probe_endpoint(a), probe_endpoint(b));
+        rounds += 1;
+
+        let a_ok = sample_errors(&sample_a).is_empty();
+        let b_ok = sample_errors(&sample_b).is_empty();
+        if a_ok {
+            stats_a.latencies.push(sample_a.latency_ms);
+        } else {
+            stats_a.errors += 1;
+        }
+        if b_ok {
+            stats_b.latencies.push(sample_b.latency_ms);
+        } else {
+            stats_b.errors += 1;
+        }
+
+        if a_ok && b_ok {
+            deltas.push(sample_a.latency_ms as i128 - sample_b.latency_ms as i128);
+            match sample_a.slot.cmp(&sample_b.slot) {
+                std::cmp::Ordering::Greater => a_ahead += 1,
+                std::cmp::Ordering::Less => b_ahead += 1,
+                std::cmp::Ordering::Equal => tied += 1,
+            }
+        }
+
+        tokio::time::sleep(options.interval).await;
+    }
+
+    println!();
+    println!("Rounds: {}", rounds);
+    print_side(a, &stats_a, rounds);
+    print_side(b, &stats_b, rounds);
+
+    if deltas.is_empty() {
+        println!("No rounds where both endpoints succeeded; nothing to compare.");
+        return Ok(());
+    }
+
+    deltas.sort_unstable();
+    let avg = deltas.iter().map(|&d| d as f64).sum::<f64>() / deltas.len() as f64;
+    println!();
+    println!("Latency delta ({} - {}, negative means {} faster):", a.nickname, b.nickname, a.nickname);
+    println!(
+        "  min {}ms  p5 {}ms  p50 {}ms  p95 {}ms  max {}ms  avg {:.1}ms",
+        deltas[0],
+        percentile(&deltas, 5.0),
+        percentile(&deltas, 50.0),
+        percentile(&deltas, 95.0),
+        deltas[deltas.len() - 1],
+        avg
+    );
+
+    let faster_a = deltas.iter().filter(|&&d| d < 0).count();
+    let compared = deltas.len() as f64;
+    println!(
+        "  {} faster in {:.1}% of rounds, {} faster in {:.1}%",
+        a.nickname,
+        faster_a as f64 * 100.0 / compared,
+        b.nickname,
+        deltas.iter().filter(|&&d| d > 0).count() as f64 * 100.0 / compared
+    );
+    println!(
+        "Slot lead: {} ahead {:.1}%, {} ahead {:.1}%, tied {:.1}%",
+        a.nickname,
+        a_ahead as f64 * 100.0 / compared,
+        b.nickname,
+        b_ahead as f64 * 100.0 / compared,
+        tied as f64 * 100.0 / compared
+    );
+
+    Ok(())
+}
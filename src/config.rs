@@ -1,26 +1,916 @@
-use crate::models::RpcEndpoint;
+use crate::alerts::{AlertMetric, Comparison};
+use crate::models::{RetryPolicy, RpcEndpoint};
+use crate::reports::ReportSchedule;
+use crate::tags::{tags_match, Tags};
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::fs;
+use std::path::{Path, PathBuf};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Default)]
 pub struct ServerConfig {
     pub listen_ip: Option<String>,
     pub port: Option<u16>,
+    /// Run only the polling/storage pipelines, without the web server
+    pub headless: Option<bool>,
+    /// Serve the dashboard from this directory instead of the embedded assets
+    pub static_dir: Option<String>,
+    /// IANA timezone for the ISO-8601 `*_iso` fields in API responses (default: "UTC")
+    pub timezone: Option<String>,
+    /// `/healthz` fails once the poller hasn't completed a round for this long (default: 120)
+    pub liveness_timeout_secs: Option<u64>,
+    /// On shutdown, wait this long for the poll round in flight to be stored (default: 30)
+    pub drain_timeout_secs: Option<u64>,
+}
+
+impl ServerConfig {
+    pub fn liveness_timeout_secs(&self) -> u64 {
+        self.liveness_timeout_secs.unwrap_or(120)
+    }
+
+    pub fn drain_timeout_secs(&self) -> u64 {
+        self.drain_timeout_secs.unwrap_or(30)
+    }
+}
+
+/// Where samples are kept
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageMode {
+    /// RocksDB at `path`
+    #[default]
+    Disk,
+    /// A bounded in-memory store instead of RocksDB: nothing is written to disk and everything is lost on exit
+    Memory,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct StorageConfig {
+    /// `disk` (default) or `memory`
+    #[serde(default)]
+    pub mode: StorageMode,
+    /// Base directory for the database and other generated files (default: CWD)
+    pub data_dir: Option<String>,
+    /// RocksDB path, relative to `data_dir` unless absolute (default: "rpc_metrics.db")
+    pub path: Option<String>,
+    /// Where `/api/admin/backup` writes checkpoints, relative to `data_dir` unless absolute (default: "backups")
+    pub backup_dir: Option<String>,
+    /// In memory mode, the newest raw samples kept before the oldest are dropped (default: 100000)
+    pub max_samples: Option<usize>,
+}
+
+impl StorageConfig {
+    /// Resolve a path against `data_dir`; absolute paths are returned unchanged
+    pub fn resolve(&self, path: &str) -> PathBuf {
+        let base = PathBuf::from(self.data_dir.as_deref().unwrap_or("."));
+        base.join(path)
+    }
+
+    pub fn in_memory(&self) -> bool {
+        self.mode == StorageMode::Memory
+    }
+
+    pub fn max_samples(&self) -> usize {
+        self.max_samples.unwrap_or(100_000)
+    }
+
+    pub fn db_path(&self) -> PathBuf {
+        self.resolve(self.path.as_deref().unwrap_or("rpc_metrics.db"))
+    }
+
+    pub fn backup_dir(&self) -> PathBuf {
+        self.resolve(self.backup_dir.as_deref().unwrap_or("backups"))
+    }
 }
 
 #[derive(Deserialize, Debug)]
 pub struct RpcConfig {
     pub endpoints: Vec<RpcEndpoint>,
+    /// Default outbound proxy for endpoints that don't set their own
+    pub proxy: Option<String>,
+    /// Default for endpoints that don't set their own `compare_protocols`
+    #[serde(default)]
+    pub compare_protocols: bool,
+    /// Default retry policy for endpoints that don't set their own
+    pub retry: Option<RetryPolicy>,
+}
+
+impl RpcConfig {
+    /// Fill in the `[rpc]` defaults an endpoint doesn't set itself
+    pub fn apply_defaults(&self, endpoint: &mut RpcEndpoint) {
+        if endpoint.proxy.is_none() {
+            endpoint.proxy = self.proxy.clone();
+        }
+        endpoint.compare_protocols.get_or_insert(self.compare_protocols);
+        if endpoint.retry.is_none() {
+            endpoint.retry = self.retry.clone();
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct LoggingConfig {
+    /// Also write logs to this file (relative to `storage.data_dir` unless absolute)
+    pub file: Option<String>,
+    /// Rotate once the file reaches this size; 0 disables size rotation (default: 100)
+    pub max_size_mb: Option<u64>,
+    /// Rotate at local midnight (default: false)
+    pub rotate_daily: Option<bool>,
+    /// Number of rotated files to keep (default: 5)
+    pub max_files: Option<usize>,
+}
+
+/// How long to keep raw samples and their downsampled rollups
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct RetentionConfig {
+    /// Keep raw samples this long before rolling them into 1-minute buckets (default: 1)
+    pub raw_hours: Option<u64>,
+    /// Keep 1-minute buckets this long before rolling them into 1-hour buckets (default: 24)
+    pub minute_hours: Option<u64>,
+    /// Keep 1-hour buckets this long (default: 30)
+    pub hour_days: Option<u64>,
+}
+
+impl RetentionConfig {
+    pub fn raw_secs(&self) -> i64 {
+        self.raw_hours.unwrap_or(1) as i64 * 3600
+    }
+
+    pub fn minute_secs(&self) -> i64 {
+        self.minute_hours.unwrap_or(24) as i64 * 3600
+    }
+
+    pub fn hour_secs(&self) -> i64 {
+        self.hour_days.unwrap_or(30) as i64 * 86400
+    }
+}
+
+/// Low-frequency probes that run alongside the main poller
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct ProbesConfig {
+    pub simulate: Option<SimulateProbeConfig>,
+    pub signature_status: Option<SignatureStatusProbeConfig>,
+    pub account_consistency: Option<AccountConsistencyProbeConfig>,
+    pub archive_depth: Option<ArchiveDepthProbeConfig>,
+    #[serde(default)]
+    pub heavy: Vec<HeavyProbeConfig>,
+    pub account_subscribe: Option<AccountSubscribeProbeConfig>,
+    pub network: Option<NetworkProbeConfig>,
+    pub tpu: Option<TpuProbeConfig>,
+    pub ports: Option<PortsProbeConfig>,
+    pub vote: Option<VoteProbeConfig>,
+}
+
+/// `simulateTransaction` with a fixed transaction
+#[derive(Deserialize, Debug, Clone)]
+pub struct SimulateProbeConfig {
+    /// Base64-encoded serialized transaction (its blockhash is replaced on every call)
+    pub transaction: String,
+    /// Seconds between probes (default: 30)
+    pub interval_secs: Option<u64>,
+}
+
+impl SimulateProbeConfig {
+    pub fn interval_secs(&self) -> u64 {
+        self.interval_secs.unwrap_or(30)
+    }
+}
+
+/// `getSignatureStatuses` for a reference signature
+#[derive(Deserialize, Debug, Clone)]
+pub struct SignatureStatusProbeConfig {
+    /// Signature to look up; if unset, one is picked from the latest finalized block each round
+    pub signature: Option<String>,
+    /// Look beyond the recent status cache (needed for old fixed signatures)
+    #[serde(default)]
+    pub search_history: bool,
+    /// Seconds between probes (default: 10)
+    pub interval_secs: Option<u64>,
+}
+
+impl SignatureStatusProbeConfig {
+    pub fn interval_secs(&self) -> u64 {
+        self.interval_secs.unwrap_or(10)
+    }
+}
+
+/// Compare `getAccountInfo` for one account across all endpoints
+#[derive(Deserialize, Debug, Clone)]
+pub struct AccountConsistencyProbeConfig {
+    /// Base58 account address to watch
+    pub account: String,
+    /// Seconds between rounds (default: 30)
+    pub interval_secs: Option<u64>,
+}
+
+impl AccountConsistencyProbeConfig {
+    pub fn interval_secs(&self) -> u64 {
+        self.interval_secs.unwrap_or(30)
+    }
+}
+
+/// `getFirstAvailableBlock` (and optionally `minimumLedgerSlot`) per endpoint
+#[derive(Deserialize, Debug, Clone)]
+pub struct ArchiveDepthProbeConfig {
+    /// Also record `minimumLedgerSlot`
+    #[serde(default)]
+    pub minimum_ledger_slot: bool,
+    /// Seconds between probes (default: 3600)
+    pub interval_secs: Option<u64>,
+}
+
+impl ArchiveDepthProbeConfig {
+    pub fn interval_secs(&self) -> u64 {
+        self.interval_secs.unwrap_or(3600)
+    }
+}
+
+/// An expensive call (e.g. `getProgramAccounts` with filters) run at a low frequency
+#[derive(Deserialize, Debug, Clone)]
+pub struct HeavyProbeConfig {
+    /// Unique name the results are stored and charted under
+    pub name: String,
+    pub method: String,
+    /// JSON-RPC params array
+    pub params: Option<serde_json::Value>,
+    /// Seconds between probes (default: 600)
+    pub interval_secs: Option<u64>,
+}
+
+impl HeavyProbeConfig {
+    pub fn interval_secs(&self) -> u64 {
+        self.interval_secs.unwrap_or(600)
+    }
+}
+
+/// WebSocket `accountSubscribe` on every endpoint, comparing when notifications arrive
+#[derive(Deserialize, Debug, Clone)]
+pub struct AccountSubscribeProbeConfig {
+    /// Base58 address of a frequently updated account
+    pub account: String,
+    /// Subscription commitment (default: "confirmed")
+    pub commitment: Option<String>,
+    /// Seconds between stored summaries (default: 60)
+    pub interval_secs: Option<u64>,
+}
+
+impl AccountSubscribeProbeConfig {
+    pub fn commitment(&self) -> &str {
+        self.commitment.as_deref().unwrap_or("confirmed")
+    }
+
+    pub fn interval_secs(&self) -> u64 {
+        self.interval_secs.unwrap_or(60)
+    }
+}
+
+/// Raw TCP connect time (and optionally ICMP RTT) to each endpoint host
+#[derive(Deserialize, Debug, Clone)]
+pub struct NetworkProbeConfig {
+    /// Also ping the host with the system `ping` command
+    #[serde(default)]
+    pub icmp: bool,
+    /// Seconds between probes (default: 30)
+    pub interval_secs: Option<u64>,
+}
+
+impl NetworkProbeConfig {
+    pub fn interval_secs(&self) -> u64 {
+        self.interval_secs.unwrap_or(30)
+    }
+}
+
+/// QUIC handshake with each endpoint's configured `tpu` address
+#[derive(Deserialize, Debug, Clone)]
+pub struct TpuProbeConfig {
+    /// Seconds between probes (default: 60)
+    pub interval_secs: Option<u64>,
+}
+
+impl TpuProbeConfig {
+    pub fn interval_secs(&self) -> u64 {
+        self.interval_secs.unwrap_or(60)
+    }
+}
+
+/// TCP checks of each endpoint's extra `ports`
+#[derive(Deserialize, Debug, Clone)]
+pub struct PortsProbeConfig {
+    /// Seconds between checks (default: 30)
+    pub interval_secs: Option<u64>,
+}
+
+impl PortsProbeConfig {
+    pub fn interval_secs(&self) -> u64 {
+        self.interval_secs.unwrap_or(30)
+    }
+}
+
+/// How quickly each endpoint reflects the latest vote of one vote account
+#[derive(Deserialize, Debug, Clone)]
+pub struct VoteProbeConfig {
+    /// Base58 vote account address
+    pub vote_account: String,
+    /// Seconds between rounds (default: 2)
+    pub interval_secs: Option<u64>,
+}
+
+impl VoteProbeConfig {
+    pub fn interval_secs(&self) -> u64 {
+        self.interval_secs.unwrap_or(2)
+    }
+}
+
+/// Add public RPC nodes found through `getClusterNodes` as monitored endpoints at startup
+#[derive(Deserialize, Debug, Clone)]
+pub struct DiscoveryConfig {
+    /// Nickname of the configured endpoint to ask for the cluster nodes
+    pub seed: String,
+    /// Most nodes to add, fastest first (default: 20)
+    pub max_endpoints: Option<usize>,
+    /// Only add nodes whose "ip:port" or identity pubkey starts with one of these
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Never add nodes whose "ip:port" or identity pubkey starts with one of these
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Time a node gets to answer `getSlot` before it's left out (default: 2000)
+    pub timeout_ms: Option<u64>,
+    /// Extra tags for every discovered endpoint
+    #[serde(default)]
+    pub tags: Tags,
+}
+
+impl DiscoveryConfig {
+    pub fn max_endpoints(&self) -> usize {
+        self.max_endpoints.unwrap_or(20)
+    }
+
+    pub fn timeout_ms(&self) -> u64 {
+        self.timeout_ms.unwrap_or(2000)
+    }
+}
+
+/// Annotate poll rounds with the slot leader to correlate latency with leaders
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct LeadersConfig {
+    /// Region label per leader identity pubkey, for grouping by region
+    #[serde(default)]
+    pub regions: HashMap<String, String>,
+}
+
+/// Well-known nodes whose stake-weighted slot serves as the consensus slot
+#[derive(Deserialize, Debug, Clone)]
+pub struct ClusterReferenceConfig {
+    pub nodes: Vec<ReferenceNodeConfig>,
+    /// Seconds between slot polls (default: 2)
+    pub interval_secs: Option<u64>,
+    /// Request timeout per node in milliseconds (default: 2000)
+    pub timeout_ms: Option<u64>,
+    /// Oldest reference view used for the consensus before falling back to our endpoints (default: 10)
+    pub max_age_secs: Option<u64>,
+}
+
+impl ClusterReferenceConfig {
+    pub fn interval_secs(&self) -> u64 {
+        self.interval_secs.unwrap_or(2)
+    }
+
+    pub fn timeout_ms(&self) -> u64 {
+        self.timeout_ms.unwrap_or(2000)
+    }
+
+    pub fn max_age_secs(&self) -> u64 {
+        self.max_age_secs.unwrap_or(10)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ReferenceNodeConfig {
+    /// RPC URL of the node
+    pub url: String,
+    /// Validator identity pubkey, whose activated stake weights the node's answer
+    pub identity: String,
+}
+
+/// Detect cluster-wide slot slowdowns and skipped slots from the fleet's highest slot
+#[derive(Deserialize, Debug, Clone)]
+pub struct ClusterEventsConfig {
+    /// Seconds of slot progression judged at a time (default: 30)
+    pub window_secs: Option<u64>,
+    /// Average slot time above which slots count as slow (default: 600)
+    pub max_slot_time_ms: Option<f64>,
+    /// Share of slots without a block above which skips count as an event (default: 25)
+    pub max_skip_pct: Option<f64>,
+}
+
+impl ClusterEventsConfig {
+    pub fn window_secs(&self) -> u64 {
+        self.window_secs.unwrap_or(30).max(1)
+    }
+
+    pub fn max_slot_time_ms(&self) -> f64 {
+        self.max_slot_time_ms.unwrap_or(600.0)
+    }
+
+    pub fn max_skip_pct(&self) -> f64 {
+        self.max_skip_pct.unwrap_or(25.0)
+    }
+}
+
+/// Thresholds driving the per-endpoint healthy/degraded/down state
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct StatusConfig {
+    /// Slots behind the highest endpoint before a sample counts as degraded (default: 25)
+    pub max_slot_lag: Option<u64>,
+    /// Latency above which a sample counts as degraded (default: 1000)
+    pub max_latency_ms: Option<u128>,
+    /// Consecutive failed samples before an endpoint is marked down (default: 3)
+    pub down_after_errors: Option<u32>,
+    /// Consecutive bad samples before an endpoint is marked degraded (default: 3)
+    pub degrade_after: Option<u32>,
+    /// Consecutive better samples before an endpoint recovers (default: 5)
+    pub recover_after: Option<u32>,
+    /// Capture a traceroute when an endpoint gets worse, attached to its incident record
+    #[serde(default)]
+    pub traceroute: bool,
+    /// Hop limit for captured traceroutes (default: 30)
+    pub traceroute_max_hops: Option<u8>,
+}
+
+impl StatusConfig {
+    pub fn max_slot_lag(&self) -> u64 {
+        self.max_slot_lag.unwrap_or(25)
+    }
+
+    pub fn max_latency_ms(&self) -> u128 {
+        self.max_latency_ms.unwrap_or(1000)
+    }
+
+    pub fn down_after_errors(&self) -> u32 {
+        self.down_after_errors.unwrap_or(3).max(1)
+    }
+
+    pub fn degrade_after(&self) -> u32 {
+        self.degrade_after.unwrap_or(3).max(1)
+    }
+
+    pub fn recover_after(&self) -> u32 {
+        self.recover_after.unwrap_or(5).max(1)
+    }
+
+    pub fn traceroute_hops(&self) -> Option<u8> {
+        self.traceroute.then(|| self.traceroute_max_hops.unwrap_or(30))
+    }
+}
+
+/// Periodic end-to-end confirmation probe: send a transaction via a test-capable
+/// endpoint and time how long every endpoint takes to report its signature
+#[derive(Deserialize, Debug, Clone)]
+pub struct ConfirmationConfig {
+    /// Fee payer keypair file (JSON array, as written by `solana-keygen`)
+    pub keypair: String,
+    /// Seconds between test transactions (default: 300)
+    pub interval_secs: Option<u64>,
+    /// Delay between getSignatureStatuses polls (default: 200)
+    pub poll_ms: Option<u64>,
+    /// Give up on an endpoint after this many seconds (default: 60)
+    pub timeout_secs: Option<u64>,
+}
+
+impl ConfirmationConfig {
+    pub fn interval_secs(&self) -> u64 {
+        self.interval_secs.unwrap_or(300)
+    }
+
+    pub fn poll_ms(&self) -> u64 {
+        self.poll_ms.unwrap_or(200)
+    }
+
+    pub fn timeout_secs(&self) -> u64 {
+        self.timeout_secs.unwrap_or(60)
+    }
+}
+
+/// Settings for `--agent` mode: probe locally and ship results to an aggregator
+#[derive(Deserialize, Debug, Clone)]
+pub struct AgentConfig {
+    /// Base URL of the aggregating instance, e.g. "https://monitor.example.com"
+    pub aggregator_url: String,
+    /// Shared secret matching the aggregator's `aggregator.token`
+    pub token: String,
+    /// Region label attached to every sample, e.g. "fra"
+    pub region: String,
+}
+
+/// Accept samples from remote agents on `POST /api/agent/samples`
+#[derive(Deserialize, Debug)]
+pub struct AggregatorConfig {
+    pub token: String,
+}
+
+/// Accept samples from external probes on `POST /api/ingest`
+#[derive(Deserialize, Debug)]
+pub struct IngestConfig {
+    /// Bearer tokens allowed to push samples
+    pub tokens: Vec<String>,
+}
+
+/// Enables the `/api/admin/*` routes for requests bearing this token
+#[derive(Deserialize, Debug)]
+pub struct AdminConfig {
+    pub token: String,
+}
+
+/// Who may read the metrics APIs
+#[derive(Deserialize, Debug, Default)]
+pub struct AccessConfig {
+    /// Serve the full fleet to callers without a token (default: true)
+    pub public: Option<bool>,
+    /// Tokens that only see a subset of the endpoints
+    #[serde(default)]
+    pub keys: Vec<ApiKeyConfig>,
+    /// Show "Provider A", "Provider B", ... instead of nicknames to everyone but the admin (default: false)
+    pub anonymize: Option<bool>,
+}
+
+impl AccessConfig {
+    pub fn public(&self) -> bool {
+        self.public.unwrap_or(true)
+    }
+
+    pub fn anonymize(&self) -> bool {
+        self.anonymize.unwrap_or(false)
+    }
+}
+
+/// A bearer token scoped to some endpoints, e.g. one customer's
+#[derive(Deserialize, Debug)]
+pub struct ApiKeyConfig {
+    pub token: String,
+    /// Endpoints visible by nickname
+    #[serde(default)]
+    pub nicknames: Vec<String>,
+    /// Endpoints visible if they carry all of these tags
+    #[serde(default)]
+    pub tags: Tags,
+}
+
+/// Declarative alert rules evaluated over recent samples
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct AlertsConfig {
+    /// How often the rules are evaluated (default: 10s)
+    pub interval_secs: Option<u64>,
+    /// URL to POST each alert event to as JSON
+    pub webhook_url: Option<String>,
+    /// Default for rules that don't set their own `cooldown_secs`
+    pub cooldown_secs: Option<u64>,
+    #[serde(default)]
+    pub rules: Vec<AlertRuleConfig>,
+}
+
+impl AlertsConfig {
+    pub fn interval_secs(&self) -> u64 {
+        self.interval_secs.unwrap_or(10)
+    }
+
+    // Rules with the section-wide cooldown filled in where they don't set one
+    pub fn resolved_rules(&self) -> Vec<AlertRuleConfig> {
+        self.rules
+            .iter()
+            .cloned()
+            .map(|mut rule| {
+                rule.cooldown_secs = rule.cooldown_secs.or(self.cooldown_secs);
+                rule
+            })
+            .collect()
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AlertRuleConfig {
+    pub name: String,
+    pub metric: AlertMetric,
+    pub comparison: Comparison,
+    pub threshold: f64,
+    /// How long the condition must hold before the rule fires (default: 0, the latest sample alone)
+    pub duration_secs: Option<u64>,
+    /// Minimum time between firing notifications for the same endpoint (default: 300s)
+    pub cooldown_secs: Option<u64>,
+    /// Only apply to these nicknames (default: all endpoints)
+    #[serde(default)]
+    pub endpoints: Vec<String>,
+    /// Only apply to endpoints carrying all of these tags
+    #[serde(default)]
+    pub tags: Tags,
+}
+
+impl AlertRuleConfig {
+    pub fn duration_secs(&self) -> u64 {
+        self.duration_secs.unwrap_or(0)
+    }
+
+    pub fn cooldown_secs(&self) -> u64 {
+        self.cooldown_secs.unwrap_or(300)
+    }
+
+    pub fn selects(&self, nickname: &str, tags: &Tags) -> bool {
+        let filter: Vec<(String, String)> = self.tags.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        (self.endpoints.is_empty() || self.endpoints.iter().any(|n| n == nickname)) && tags_match(tags, &filter)
+    }
+}
+
+/// Stop probing endpoints that keep failing outright, re-testing them periodically
+#[derive(Deserialize, Debug, Clone)]
+pub struct BreakerConfig {
+    /// Consecutive failed probes before the breaker opens (default: 5)
+    pub failures: Option<u32>,
+    /// How long the breaker stays open before a re-test (default: 60s)
+    pub open_secs: Option<u64>,
+    /// Upper bound for the open period, which doubles after each failed re-test (default: 600s)
+    pub max_open_secs: Option<u64>,
+}
+
+impl BreakerConfig {
+    pub fn failures(&self) -> u32 {
+        self.failures.unwrap_or(5).max(1)
+    }
+
+    pub fn open_secs(&self) -> u64 {
+        self.open_secs.unwrap_or(60)
+    }
+
+    pub fn max_open_secs(&self) -> u64 {
+        self.max_open_secs.unwrap_or(600).max(self.open_secs())
+    }
+}
+
+/// Flag latency samples far outside each endpoint's own recent baseline
+#[derive(Deserialize, Debug, Clone)]
+pub struct AnomalyConfig {
+    /// Deviations at least this many standard deviations from the baseline are flagged (default: 3.0)
+    pub z_score: Option<f64>,
+    /// EWMA smoothing factor; smaller values give a longer memory (default: 0.05)
+    pub alpha: Option<f64>,
+    /// Samples to learn from before flagging anything (default: 30)
+    pub warmup_samples: Option<u32>,
+    /// Floor on the standard deviation so very steady endpoints don't flag 1ms blips (default: 5ms)
+    pub min_stddev_ms: Option<f64>,
+}
+
+impl AnomalyConfig {
+    pub fn z_score(&self) -> f64 {
+        self.z_score.unwrap_or(3.0)
+    }
+
+    pub fn alpha(&self) -> f64 {
+        self.alpha.unwrap_or(0.05).clamp(0.001, 1.0)
+    }
+
+    pub fn warmup_samples(&self) -> u32 {
+        self.warmup_samples.unwrap_or(30)
+    }
+
+    pub fn min_stddev_ms(&self) -> f64 {
+        self.min_stddev_ms.unwrap_or(5.0)
+    }
+}
+
+/// Scheduled per-endpoint SLA summaries
+#[derive(Deserialize, Debug)]
+pub struct ReportsConfig {
+    /// "daily" (default) or "weekly"; periods close at 00:00 UTC (Mondays for weekly)
+    #[serde(default)]
+    pub schedule: ReportSchedule,
+    /// Directory to write JSON reports to (relative to `storage.data_dir` unless absolute)
+    pub output_dir: Option<String>,
+    /// URL to POST each report to as JSON
+    pub webhook_url: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct AppConfig {
+    #[serde(default)]
     pub server: ServerConfig,
     pub rpc: RpcConfig,
+    #[serde(default)]
+    pub storage: StorageConfig,
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    #[serde(default)]
+    pub retention: RetentionConfig,
+    #[serde(default)]
+    pub status: StatusConfig,
+    #[serde(default)]
+    pub probes: ProbesConfig,
+    pub agent: Option<AgentConfig>,
+    pub aggregator: Option<AggregatorConfig>,
+    pub ingest: Option<IngestConfig>,
+    pub admin: Option<AdminConfig>,
+    #[serde(default)]
+    pub access: AccessConfig,
+    #[serde(default)]
+    pub alerts: AlertsConfig,
+    pub anomaly: Option<AnomalyConfig>,
+    pub breaker: Option<BreakerConfig>,
+    pub discovery: Option<DiscoveryConfig>,
+    pub leaders: Option<LeadersConfig>,
+    pub cluster_reference: Option<ClusterReferenceConfig>,
+    pub cluster_events: Option<ClusterEventsConfig>,
+    pub reports: Option<ReportsConfig>,
+    pub confirmation: Option<ConfirmationConfig>,
 }
 
-pub fn load_config() -> Result<AppConfig, Box<dyn std::error::Error>> {
-    let config_str = fs::read_to_string("config.toml")?;
-    let config: AppConfig = toml::from_str(&config_str)?;
+// Environment variables overriding config.toml values, e.g. SVS_MONITOR_SERVER__PORT=8080 or
+// SVS_MONITOR_RPC__ENDPOINTS__MAINNET__URL=https://...; "__" separates the path segments
+const ENV_PREFIX: &str = "SVS_MONITOR_";
+const ENV_SEPARATOR: &str = "__";
+
+/// Environment variable naming the config file when `--config` isn't given
+pub const CONFIG_ENV: &str = "SVS_MONITOR_CONFIG";
+
+// Env var segments can't hold every character a nickname can, so compare both sides reduced to
+// lowercase alphanumerics and underscores
+fn env_segment(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect()
+}
+
+// Most variables that could be read as TOML on their own, e.g. `8080`, `true` or `["a", "b"]`
+fn parse_toml_value(raw: &str) -> Option<toml::Value> {
+    toml::from_str::<toml::Table>(&format!("value = {}", raw))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+}
+
+// Variables are strings unless the file already holds a number, boolean or array at that path,
+// so a token that happens to read as `123`, `true` or `[...]` is never mistaken for one
+fn env_value(raw: &str, existing: Option<&toml::Value>) -> Result<toml::Value, String> {
+    let invalid = |expected: &str| format!("expected {}, got {:?}", expected, raw);
+    match existing {
+        Some(toml::Value::Integer(_)) => raw.trim().parse().map(toml::Value::Integer).map_err(|_| invalid("an integer")),
+        Some(toml::Value::Float(_)) => raw.trim().parse().map(toml::Value::Float).map_err(|_| invalid("a number")),
+        Some(toml::Value::Boolean(_)) => raw.trim().parse().map(toml::Value::Boolean).map_err(|_| invalid("true or false")),
+        Some(toml::Value::Array(_)) => parse_toml_value(raw)
+            .filter(|value| value.is_array())
+            .ok_or_else(|| invalid("an array")),
+        _ => Ok(toml::Value::String(raw.to_string())),
+    }
+}
+
+// Replace the value at `path` below `value` with what `leaf` makes of the current one, if any.
+// Tables are created as needed; an array element is picked by index or, for arrays of tables
+// such as the endpoints, by its `nickname`.
+fn set_path(
+    value: &mut toml::Value,
+    path: &[String],
+    leaf: &mut dyn FnMut(Option<&toml::Value>) -> Result<toml::Value, String>,
+) -> Result<(), String> {
+    let (segment, rest) = match path.split_first() {
+        Some(split) => split,
+        None => {
+            *value = leaf(Some(&*value))?;
+            return Ok(());
+        }
+    };
+    let child = match value {
+        toml::Value::Table(table) if rest.is_empty() => {
+            let new_value = leaf(table.get(segment))?;
+            table.insert(segment.clone(), new_value);
+            return Ok(());
+        }
+        toml::Value::Table(table) => table
+            .entry(segment.clone())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new())),
+        toml::Value::Array(items) => {
+            let by_index = segment.parse::<usize>().ok();
+            let position = items.iter().position(|item| {
+                item.get("nickname")
+                    .and_then(|nickname| nickname.as_str())
+                    .map_or(false, |nickname| env_segment(nickname) == *segment)
+            });
+            by_index
+                .or(position)
+                .and_then(|index| items.get_mut(index))
+                .ok_or_else(|| format!("no entry {:?}", segment))?
+        }
+        _ => return Err(format!("{:?} is not a table", segment)),
+    };
+    set_path(child, rest, leaf)
+}
+
+// A variable for a key the file doesn't set, so there was no type to go by: (path, raw value)
+type Untyped = (Vec<String>, String);
+
+// Layer SVS_MONITOR_* variables over the parsed file, so secrets can be injected by the environment.
+// Returns the variables that had to be taken as strings for lack of an existing value.
+fn apply_env_overrides(value: &mut toml::Value) -> Result<Vec<Untyped>, Box<dyn std::error::Error>> {
+    let mut untyped = Vec::new();
+    let mut overrides: Vec<(String, String)> = std::env::vars()
+        .filter(|(name, _)| name.starts_with(ENV_PREFIX) && name != CONFIG_ENV)
+        .collect();
+    // Apply in a fixed order so overlapping variables always resolve the same way
+    overrides.sort();
+    for (name, raw) in overrides {
+        let path: Vec<String> = name[ENV_PREFIX.len()..]
+            .split(ENV_SEPARATOR)
+            .map(env_segment)
+            .collect();
+        if path.iter().any(|segment| segment.is_empty()) {
+            return Err(format!("{}: malformed variable name", name).into());
+        }
+        let mut new_key = false;
+        set_path(value, &path, &mut |existing| {
+            new_key = existing.is_none();
+            env_value(&raw, existing)
+        })
+        .map_err(|e| format!("{}: {}", name, e))?;
+        if new_key {
+            untyped.push((path, raw));
+        }
+    }
+    Ok(untyped)
+}
+
+// Past this many untyped variables that could be read as TOML, the config must set the keys instead
+const MAX_RETYPED: usize = 12;
+
+// Deserialize the config. If it doesn't fit the schema with every untyped variable as a string,
+// retry with the fewest of them read as TOML values instead, so `SVS_MONITOR_SERVER__PORT=8080`
+// works whether or not the file sets `port`, and a numeric token still stays a string.
+fn deserialize_config(value: toml::Value, untyped: &[Untyped]) -> Result<AppConfig, Box<dyn std::error::Error>> {
+    let first_error = match value.clone().try_into::<AppConfig>() {
+        Ok(config) => return Ok(config),
+        Err(e) => e,
+    };
+    let candidates: Vec<(&[String], toml::Value)> = untyped
+        .iter()
+        .filter_map(|(path, raw)| Some((path.as_slice(), parse_toml_value(raw).filter(|value| !value.is_str())?)))
+        .collect();
+    if candidates.is_empty() || candidates.len() > MAX_RETYPED {
+        return Err(first_error.into());
+    }
+
+    let mut masks: Vec<u32> = (1..1u32 << candidates.len()).collect();
+    masks.sort_by_key(|mask| mask.count_ones());
+    for mask in masks {
+        let mut attempt = value.clone();
+        for (i, (path, parsed)) in candidates.iter().enumerate() {
+            if mask & (1 << i) != 0 {
+                set_path(&mut attempt, path, &mut |_| Ok(parsed.clone()))?;
+            }
+        }
+        if let Ok(config) = attempt.try_into::<AppConfig>() {
+            return Ok(config);
+        }
+    }
+    Err(first_error.into())
+}
+
+// $XDG_CONFIG_HOME/svs-rpc-monitor/config.toml, falling back to ~/.config
+fn xdg_config_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("svs-rpc-monitor").join("config.toml"))
+}
+
+/// The config file to use: `--config`, then `SVS_MONITOR_CONFIG`, then the XDG config
+/// directory, then `config.toml` in the working directory
+pub fn config_path(cli: Option<&str>) -> Result<PathBuf, String> {
+    if let Some(path) = cli {
+        return Ok(PathBuf::from(path));
+    }
+    if let Some(path) = std::env::var_os(CONFIG_ENV).filter(|path| !path.is_empty()) {
+        return Ok(PathBuf::from(path));
+    }
+    let candidates: Vec<PathBuf> = xdg_config_path()
+        .into_iter()
+        .chain(std::iter::once(PathBuf::from("config.toml")))
+        .collect();
+    candidates.iter().find(|path| path.is_file()).cloned().ok_or_else(|| {
+        let searched: Vec<String> = candidates.iter().map(|path| path.display().to_string()).collect();
+        format!(
+            "No config file found (looked for {}); pass --config or set {}",
+            searched.join(", "),
+            CONFIG_ENV
+        )
+    })
+}
+
+pub fn load_config(path: &Path) -> Result<AppConfig, Box<dyn std::error::Error>> {
+    let config_str = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut value: toml::Value = toml::from_str(&config_str)?;
+    let untyped = apply_env_overrides(&mut value)?;
+    let mut config = deserialize_config(value, &untyped)?;
+
+    let mut endpoints = std::mem::take(&mut config.rpc.endpoints);
+    for endpoint in endpoints.iter_mut() {
+        config.rpc.apply_defaults(endpoint);
+    }
+    config.rpc.endpoints = endpoints;
+
     Ok(config)
 }
@@ -0,0 +1,205 @@
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use base64::Engine;
+use solana_sdk::hash::Hash;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::access::{allowed, resolve_scope};
+use crate::config::ConfirmationConfig;
+use crate::models::RpcEndpoint;
+use crate::pause;
+use crate::rpc::call_method;
+use crate::state::AppState;
+use crate::storage::{scan_prefix, Store, CONFIRMATIONS_PREFIX};
+use crate::usage;
+
+/// One end-to-end run: a transaction sent via `sender` and when each endpoint first reported it
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConfirmationRecord {
+    /// Unix milliseconds; records from before the switch hold float seconds under "timestamp"
+    #[serde(alias = "timestamp", deserialize_with = "crate::timestamps::deserialize_millis")]
+    pub timestamp_ms: i64,
+    pub signature: String,
+    pub sender: String,
+    /// Milliseconds from send until the endpoint returned a status; None if it never did
+    pub visibility_ms: BTreeMap<String, Option<u128>>,
+}
+
+// Send a 0-lamport self-transfer; each run uses a fresh blockhash so signatures never repeat.
+// Both calls go through the sender's configured client, so its proxy/TLS/protocol settings apply.
+async fn send_test_transaction(sender: &RpcEndpoint, payer: &Keypair) -> Result<String, Box<dyn std::error::Error>> {
+    let params = Some(json!([{"commitment": "finalized"}]));
+    let (response, _) = call_method::<Value>(sender, "getLatestBlockhash", params).await.map_err(|e| e.detail)?;
+    let blockhash = response
+        .get("value")
+        .and_then(|value| value.get("blockhash"))
+        .and_then(Value::as_str)
+        .ok_or("Missing blockhash in getLatestBlockhash response")?;
+    let blockhash = Hash::from_str(blockhash)?;
+
+    let instruction = system_instruction::transfer(&payer.pubkey(), &payer.pubkey(), 0);
+    let transaction = Transaction::new_signed_with_payer(&[instruction], Some(&payer.pubkey()), &[payer], blockhash);
+    let encoded = base64::engine::general_purpose::STANDARD.encode(bincode::serialize(&transaction)?);
+    let params = Some(json!([encoded, {"encoding": "base64", "skipPreflight": true}]));
+    let (signature, _) = call_method::<String>(sender, "sendTransaction", params).await.map_err(|e| e.detail)?;
+    Ok(signature)
+}
+
+// Poll one endpoint until it knows about the signature or the timeout passes
+async fn wait_for_signature(endpoint: &RpcEndpoint, signature: &str, started: Instant, poll: Duration, timeout: Duration) -> Option<u128> {
+    while started.elapsed() < timeout {
+        let params = Some(json!([[signature], {"searchTransactionHistory": false}]));
+        if let Ok((response, _)) = call_method::<Value>(endpoint, "getSignatureStatuses", params).await {
+            let found = response
+                .get("value")
+                .and_then(|value| value.get(0))
+                .map_or(false, |status| !status.is_null());
+            if found {
+                return Some(started.elapsed().as_millis());
+            }
+        }
+        tokio::time::sleep(poll).await;
+    }
+    None
+}
+
+async fn run_once(
+    sender: &RpcEndpoint,
+    endpoints: &[RpcEndpoint],
+    payer: &Keypair,
+    config: &ConfirmationConfig,
+) -> Result<ConfirmationRecord, Box<dyn std::error::Error>> {
+    // The sender was just let through; asking again could throttle it out of its own run
+    let endpoints: Vec<&RpcEndpoint> = endpoints
+        .iter()
+        .filter(|e| e.nickname == sender.nickname || (!pause::is_paused(&e.nickname) && usage::allow_probe(e)))
+        .collect();
+    let timestamp_ms = chrono::Utc::now().timestamp_millis();
+    let signature = send_test_transaction(sender, payer).await?;
+    let started = Instant::now();
+    let poll = Duration::from_millis(config.poll_ms());
+    let timeout = Duration::from_secs(config.timeout_secs());
+
+    let delays = futures::future::join_all(
+        endpoints
+            .iter()
+            .map(|endpoint| wait_for_signature(endpoint, &signature, started, poll, timeout)),
+    )
+    .await;
+
+    Ok(ConfirmationRecord {
+        timestamp_ms,
+        signature,
+        sender: sender.nickname.clone(),
+        visibility_ms: endpoints
+            .iter()
+            .map(|endpoint| endpoint.nickname.clone())
+            .zip(delays)
+            .collect(),
+    })
+}
+
+// The first sender from `round` on in rotation that is neither paused nor over its budget
+fn next_sender(senders: &[RpcEndpoint], round: usize) -> Option<&RpcEndpoint> {
+    (0..senders.len())
+        .map(|offset| &senders[(round + offset) % senders.len()])
+        .find(|sender| !pause::is_paused(&sender.nickname) && usage::allow_probe(sender))
+}
+
+// Periodically send a test transaction, rotating through the test-capable endpoints as sender
+pub fn spawn_confirmation_probe(config: ConfirmationConfig, endpoints: Vec<RpcEndpoint>, db: Arc<Store>) -> Result<(), Box<dyn std::error::Error>> {
+    let payer = read_keypair_file(&config.keypair)
+        .map_err(|e| format!("Failed to read keypair {}: {}", config.keypair, e))?;
+    let senders: Vec<RpcEndpoint> = endpoints.iter().filter(|e| e.test_capable).cloned().collect();
+    if senders.is_empty() {
+        return Err("[confirmation] needs at least one endpoint with test_capable = true".into());
+    }
+
+    tokio::spawn(async move {
+        let mut round = 0;
+        loop {
+            let sender = match next_sender(&senders, round) {
+                Some(sender) => sender,
+                None => {
+                    log_info!("Skipping confirmation probe: every test-capable endpoint is paused or over its request budget");
+                    tokio::time::sleep(Duration::from_secs(config.interval_secs())).await;
+                    continue;
+                }
+            };
+            round += 1;
+            match run_once(sender, &endpoints, &payer, &config).await {
+                Ok(record) => {
+                    log_info!("Confirmation probe {} via {}: {:?}", record.signature, record.sender, record.visibility_ms);
+                    let key = format!("{}{}", CONFIRMATIONS_PREFIX, record.timestamp_ms);
+                    match serde_json::to_vec(&record) {
+                        Ok(value) => {
+                            if let Err(e) = db.put(key.as_bytes(), value) {
+                                log_error!("Failed to store confirmation probe: {}", e);
+                            }
+                        }
+                        Err(e) => log_error!("Failed to serialize confirmation probe: {}", e),
+                    }
+                }
+                Err(e) => log_error!("Confirmation probe via {} failed: {}", sender.nickname, e),
+            }
+            tokio::time::sleep(Duration::from_secs(config.interval_secs())).await;
+        }
+    });
+    Ok(())
+}
+
+// Recent end-to-end runs, newest first. Optional `limit` param (default 50).
+// Scoped keys only see the endpoints they are allowed; runs with none left are dropped.
+pub async fn get_confirmations(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<ConfirmationRecord>>, StatusCode> {
+    let scope = resolve_scope(&state, &headers)?;
+    let limit = params
+        .get("limit")
+        .and_then(|l| l.parse::<usize>().ok())
+        .unwrap_or(50);
+
+    let visible = |nickname: &str| {
+        state
+            .config
+            .rpc
+            .endpoints
+            .iter()
+            .find(|e| e.nickname == nickname)
+            .map_or(false, |e| allowed(&scope, &e.nickname, &e.tags))
+    };
+
+    let mut records: Vec<ConfirmationRecord> = scan_prefix::<ConfirmationRecord>(&state.db, CONFIRMATIONS_PREFIX)
+        .into_iter()
+        .map(|(_, record)| record)
+        .filter_map(|mut record| {
+            if scope.is_some() {
+                record.visibility_ms.retain(|nickname, _| visible(nickname));
+                if record.visibility_ms.is_empty() {
+                    return None;
+                }
+                if !visible(&record.sender) {
+                    record.sender = String::new();
+                }
+            }
+            Some(record)
+        })
+        .collect();
+    records.reverse();
+    records.truncate(limit);
+
+    Ok(Json(records))
+}
@@ -0,0 +1,114 @@
+use futures::future::join_all;
+use serde::Deserialize;
+use std::net::{IpAddr, SocketAddr};
+
+use crate::config::{DiscoveryConfig, RpcConfig};
+use crate::models::RpcEndpoint;
+use crate::rpc::{call_method, time_method};
+
+// Nodes are checked this many at a time so a large cluster doesn't open hundreds of connections at once
+const CHECK_BATCH: usize = 32;
+
+// The parts of a `getClusterNodes` entry discovery uses
+#[derive(Debug, Deserialize)]
+struct ClusterNode {
+    pubkey: String,
+    rpc: Option<String>,
+    version: Option<String>,
+}
+
+// Only RPC ports reachable from the internet are worth monitoring
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => !(ip.is_private() || ip.is_loopback() || ip.is_link_local() || ip.is_unspecified()),
+        IpAddr::V6(ip) => !(ip.is_loopback() || ip.is_unspecified()),
+    }
+}
+
+fn matches(patterns: &[String], node: &ClusterNode, rpc: &str) -> bool {
+    patterns
+        .iter()
+        .any(|pattern| rpc.starts_with(pattern.as_str()) || node.pubkey.starts_with(pattern.as_str()))
+}
+
+fn to_endpoint(config: &DiscoveryConfig, node: &ClusterNode, rpc: &str) -> RpcEndpoint {
+    let mut tags = config.tags.clone();
+    tags.insert("source".to_string(), "discovery".to_string());
+    tags.insert("pubkey".to_string(), node.pubkey.clone());
+    if let Some(version) = &node.version {
+        tags.insert("version".to_string(), version.clone());
+    }
+    RpcEndpoint {
+        url: format!("http://{}", rpc),
+        nickname: format!("node-{}", &node.pubkey[..node.pubkey.len().min(8)]),
+        tags,
+        ..Default::default()
+    }
+}
+
+/// Ask the seed endpoint for the cluster's nodes and return the responsive public RPC ports
+/// as endpoints, fastest first, up to `max_endpoints`. Nodes already in the config are skipped.
+pub async fn discover(config: &DiscoveryConfig, rpc: &RpcConfig) -> Result<Vec<RpcEndpoint>, String> {
+    let seed = rpc
+        .endpoints
+        .iter()
+        .find(|e| e.nickname == config.seed)
+        .ok_or_else(|| format!("Discovery seed {} is not a configured endpoint", config.seed))?;
+    let (nodes, _) = call_method::<Vec<ClusterNode>>(seed, "getClusterNodes", None)
+        .await
+        .map_err(|e| format!("getClusterNodes on {} failed: {}", seed.nickname, e))?;
+
+    let candidates: Vec<RpcEndpoint> = nodes
+        .iter()
+        .filter_map(|node| {
+            let rpc_addr = node.rpc.as_deref()?;
+            let addr: SocketAddr = rpc_addr.parse().ok()?;
+            let wanted = is_public(addr.ip())
+                && (config.allow.is_empty() || matches(&config.allow, node, rpc_addr))
+                && !matches(&config.deny, node, rpc_addr);
+            wanted.then(|| to_endpoint(config, node, rpc_addr))
+        })
+        .filter(|candidate| {
+            !rpc.endpoints
+                .iter()
+                .any(|e| e.url.trim_end_matches('/') == candidate.url || e.nickname == candidate.nickname)
+        })
+        .collect();
+    log_info!(
+        "🔎 {} of {} cluster nodes expose a public RPC port; checking which respond",
+        candidates.len(),
+        nodes.len()
+    );
+
+    let mut responsive = Vec::new();
+    for batch in candidates.chunks(CHECK_BATCH) {
+        let timings = join_all(batch.iter().map(|candidate| {
+            let check = RpcEndpoint {
+                timeout_ms: Some(config.timeout_ms()),
+                connect_timeout_ms: Some(config.timeout_ms()),
+                ..candidate.clone()
+            };
+            async move { time_method(&check, "getSlot", None).await }
+        }))
+        .await;
+        for (candidate, timing) in batch.iter().zip(timings) {
+            if let Ok(latency) = timing {
+                responsive.push((latency, candidate.clone()));
+            }
+        }
+    }
+    responsive.sort_by_key(|(latency, _)| *latency);
+
+    let discovered: Vec<RpcEndpoint> = responsive
+        .into_iter()
+        .take(config.max_endpoints())
+        .map(|(_, mut endpoint)| {
+            rpc.apply_defaults(&mut endpoint);
+            endpoint
+        })
+        .collect();
+    for endpoint in &discovered {
+        log_info!("🔎 Discovered {} at {}", endpoint.nickname, endpoint.url);
+    }
+    Ok(discovered)
+}
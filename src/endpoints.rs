@@ -0,0 +1,97 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::Serialize;
+
+use crate::access::{allowed, resolve_scope};
+use crate::anonymize;
+use crate::breaker::{self, BreakerStatus};
+use crate::models::{RPCResponse, RpcEndpoint};
+use crate::probes::{latest_result, ProbeResult};
+use crate::protocol_stats::{self, ProtocolStats};
+use crate::state::AppState;
+use crate::status::{self, EndpointStatus};
+use crate::storage::last_in_prefix;
+
+#[derive(Debug, Serialize)]
+pub struct EndpointDetail {
+    pub nickname: String,
+    pub status: Option<EndpointStatus>,
+    /// Circuit breaker state, when `[breaker]` is enabled
+    pub breaker: Option<BreakerStatus>,
+    pub latest_sample: Option<RPCResponse>,
+    /// Latest result of each configured extra port check
+    pub ports: Vec<ProbeResult>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EndpointStats {
+    pub nickname: String,
+    /// Probes by how they were served, since the database was created
+    pub protocols: ProtocolStats,
+    pub total: u64,
+    /// Share of probes that needed the HTTP/1.1 or legacy client fallback
+    pub fallback_pct: f64,
+}
+
+// The configured endpoint by nickname, as long as the caller may see it
+pub fn find_endpoint<'a>(state: &'a AppState, headers: &HeaderMap, nickname: &str) -> Result<&'a RpcEndpoint, StatusCode> {
+    let scope = resolve_scope(state, headers)?;
+    let nickname = anonymize::real_nickname(state, headers, nickname);
+    state
+        .config
+        .rpc
+        .endpoints
+        .iter()
+        .find(|e| e.nickname == nickname && allowed(&scope, &e.nickname, &e.tags))
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+// Everything known about one configured endpoint
+pub async fn get_endpoint(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(nickname): Path<String>,
+) -> Result<Json<EndpointDetail>, StatusCode> {
+    let endpoint = find_endpoint(&state, &headers, &nickname)?;
+    let nickname = endpoint.nickname.clone();
+
+    // Local samples are keyed "<nickname>:<ts>", so the last one in key order is the newest
+    let latest_sample = last_in_prefix::<RPCResponse>(&state.db, &format!("{}:", nickname)).map(|(_, mut sample)| {
+        sample.rpc_url = String::new();
+        sample
+    });
+
+    let ports = endpoint
+        .ports
+        .iter()
+        .filter_map(|check| latest_result(&state.db, &format!("port_{}", check.name), &nickname))
+        .collect();
+
+    Ok(Json(EndpointDetail {
+        status: status::snapshot().remove(&nickname),
+        breaker: breaker::status(&nickname),
+        nickname,
+        latest_sample,
+        ports,
+    }))
+}
+
+// Protocol and fallback counters for one configured endpoint
+pub async fn get_endpoint_stats(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(nickname): Path<String>,
+) -> Result<Json<EndpointStats>, StatusCode> {
+    let nickname = find_endpoint(&state, &headers, &nickname)?.nickname.clone();
+
+    let protocols = protocol_stats::get(&nickname);
+    Ok(Json(EndpointStats {
+        total: protocols.total(),
+        fallback_pct: protocols.fallback_pct(),
+        protocols,
+        nickname,
+    }))
+}
@@ -0,0 +1,55 @@
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+// Weak, since compression changes the bytes on the wire but not the content
+fn etag_for(body: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    format!("W/\"{:016x}\"", hasher.finish())
+}
+
+fn matches(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag.trim_start_matches("W/"))
+}
+
+/// Serialize `body` as JSON with an ETag, answering 304 Not Modified when the client already
+/// holds the same content (`If-None-Match`). Pollers that ask every few seconds then skip the
+/// body whenever nothing was stored in between.
+pub fn json_with_etag<T: Serialize>(headers: &HeaderMap, body: &T) -> Result<Response, StatusCode> {
+    let bytes = serde_json::to_vec(body).map_err(|e| {
+        log_error!("Error serializing response: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let etag = etag_for(&bytes);
+    let etag_header = HeaderValue::from_str(&etag).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    // Always revalidate, so a cached copy is never shown without asking first
+    let cache_control = HeaderValue::from_static("no-cache");
+
+    if matches(headers, &etag) {
+        return Ok((
+            StatusCode::NOT_MODIFIED,
+            [(header::ETAG, etag_header), (header::CACHE_CONTROL, cache_control)],
+        )
+            .into_response());
+    }
+    Ok((
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static("application/json")),
+            (header::ETAG, etag_header),
+            (header::CACHE_CONTROL, cache_control),
+        ],
+        bytes,
+    )
+        .into_response())
+}
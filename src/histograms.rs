@@ -0,0 +1,200 @@
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::Response,
+};
+use chrono::Utc;
+use hdrhistogram::serialization::{Deserializer, Serializer, V2Serializer};
+use hdrhistogram::Histogram;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::access::{allowed, resolve_scope};
+use crate::check::sample_errors;
+use crate::etag::json_with_etag;
+use crate::models::RPCResponse;
+use crate::state::AppState;
+use crate::storage::{scan_prefix_raw, Store, HISTOGRAMS_PREFIX};
+use crate::timestamps::parse_time;
+
+// Latencies are tracked from 1ms to 60s with 3 significant digits; anything slower is
+// clamped to the top bucket
+const HIGHEST_LATENCY_MS: u64 = 60_000;
+const SIGNIFICANT_DIGITS: u8 = 3;
+const BUCKET_SECS: i64 = 60;
+
+// Per-minute histograms keyed by sample label and minute start, held until their minute has
+// ended and been flushed
+static CURRENT: Lazy<Mutex<HashMap<(String, i64), Histogram<u64>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn new_histogram() -> Histogram<u64> {
+    Histogram::new_with_bounds(1, HIGHEST_LATENCY_MS, SIGNIFICANT_DIGITS).expect("valid histogram bounds")
+}
+
+fn bucket_start(timestamp: f64) -> i64 {
+    let secs = timestamp as i64;
+    secs - secs.rem_euclid(BUCKET_SECS)
+}
+
+fn histogram_key(label: &str, bucket: i64) -> String {
+    format!("{}{}:{}", HISTOGRAMS_PREFIX, label, bucket)
+}
+
+// Label and bucket start of a stored histogram
+fn parse_key(key: &[u8]) -> Option<(String, i64)> {
+    let key = String::from_utf8_lossy(key);
+    let (label, bucket) = key.strip_prefix(HISTOGRAMS_PREFIX)?.rsplit_once(':')?;
+    Some((label.to_string(), bucket.parse().ok()?))
+}
+
+// Fold the latency of each successful sample into its minute's histogram
+pub fn record_round(samples: &[RPCResponse]) {
+    let mut current = CURRENT.lock().unwrap();
+    for sample in samples.iter().filter(|sample| sample_errors(sample).is_empty()) {
+        let latency = (sample.latency_ms as u64).max(1);
+        current
+            .entry((sample.label(), bucket_start(sample.timestamp_secs())))
+            .or_insert_with(new_histogram)
+            .saturating_record(latency);
+    }
+}
+
+// Write every histogram to the database once a minute; finished minutes are then dropped
+// from memory while the one in progress keeps accumulating
+pub fn spawn_flush(db: Arc<Store>) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(BUCKET_SECS as u64)).await;
+            let in_progress = bucket_start(Utc::now().timestamp() as f64);
+
+            let mut current = CURRENT.lock().unwrap();
+            for ((label, bucket), histogram) in current.iter() {
+                let mut encoded = Vec::new();
+                if let Err(e) = V2Serializer::new().serialize(histogram, &mut encoded) {
+                    log_error!("Error encoding latency histogram for {}: {:?}", label, e);
+                    continue;
+                }
+                if let Err(e) = db.put(histogram_key(label, *bucket).as_bytes(), encoded) {
+                    log_error!("Error storing latency histogram for {}: {}", label, e);
+                }
+            }
+            current.retain(|(_, bucket), _| *bucket >= in_progress);
+        }
+    });
+}
+
+/// Latency at `quantile` (0-1) for one sample label over the last `minutes` minute buckets,
+/// the one in progress included
+pub fn recent_quantile(db: &Store, label: &str, minutes: i64, quantile: f64) -> Option<u64> {
+    let newest = bucket_start(Utc::now().timestamp() as f64);
+    let current = CURRENT.lock().unwrap();
+    let mut merged = new_histogram();
+    for bucket in (0..minutes).map(|minute| newest - minute * BUCKET_SECS) {
+        if let Some(histogram) = current.get(&(label.to_string(), bucket)) {
+            let _ = merged.add(histogram);
+            continue;
+        }
+        let stored = db.get(histogram_key(label, bucket).as_bytes()).ok().flatten();
+        if let Some(histogram) = stored.and_then(|value| Deserializer::new().deserialize::<u64, _>(&mut value.as_slice()).ok()) {
+            let _ = merged.add(&histogram);
+        }
+    }
+    (!merged.is_empty()).then(|| merged.value_at_quantile(quantile))
+}
+
+#[derive(Debug, Serialize)]
+pub struct LatencyPercentiles {
+    pub label: String,
+    pub count: u64,
+    pub min: u64,
+    pub max: u64,
+    pub mean: f64,
+    /// Requested percentiles keyed as e.g. "p99.9"
+    pub percentiles: BTreeMap<String, u64>,
+}
+
+// Exact latency percentiles over any window, merged from the per-minute histograms.
+// Params: `rpc` (sample label), `from`/`to` (unix seconds, millis or RFC 3339, default: the last hour) and
+// `p` (comma-separated percentiles, default "50,90,99,99.9").
+pub async fn get_percentiles(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Response, StatusCode> {
+    let scope = resolve_scope(&state, &headers)?;
+
+    let now = Utc::now().timestamp();
+    let from = params
+        .get("from")
+        .and_then(|ts| parse_time(ts))
+        .unwrap_or(now - 3600);
+    let to = params.get("to").and_then(|ts| parse_time(ts)).unwrap_or(now);
+    let quantiles: Vec<f64> = params
+        .get("p")
+        .map(|p| p.as_str())
+        .unwrap_or("50,90,99,99.9")
+        .split(',')
+        .filter_map(|p| p.trim().parse::<f64>().ok())
+        .filter(|p| (0.0..=100.0).contains(p))
+        .collect();
+
+    let visible = |label: &str| {
+        let nickname = label.split('@').next().unwrap_or(label);
+        params.get("rpc").map_or(true, |rpc| rpc == label)
+            && state
+                .config
+                .rpc
+                .endpoints
+                .iter()
+                .find(|e| e.nickname == nickname)
+                .map_or(scope.is_none(), |e| allowed(&scope, &e.nickname, &e.tags))
+    };
+    let in_window = |bucket: i64| bucket + BUCKET_SECS > from && bucket <= to;
+
+    // Buckets still in memory may have been flushed part way through, so they are taken
+    // from memory and their stored copies are skipped
+    let current = CURRENT.lock().unwrap().clone();
+    let mut merged: BTreeMap<String, Histogram<u64>> = BTreeMap::new();
+    let prefix = match params.get("rpc") {
+        Some(label) => format!("{}{}:", HISTOGRAMS_PREFIX, label),
+        None => HISTOGRAMS_PREFIX.to_string(),
+    };
+    for (key, value) in scan_prefix_raw(&state.db, &prefix) {
+        let (label, bucket) = match parse_key(&key) {
+            Some(parsed) => parsed,
+            None => continue,
+        };
+        if !in_window(bucket) || !visible(&label) || current.contains_key(&(label.clone(), bucket)) {
+            continue;
+        }
+        if let Ok(histogram) = Deserializer::new().deserialize::<u64, _>(&mut value.as_slice()) {
+            let _ = merged.entry(label).or_insert_with(new_histogram).add(&histogram);
+        }
+    }
+    for ((label, bucket), histogram) in &current {
+        if in_window(*bucket) && visible(label) {
+            let _ = merged.entry(label.clone()).or_insert_with(new_histogram).add(histogram);
+        }
+    }
+
+    let results = merged
+        .into_iter()
+        .filter(|(_, histogram)| !histogram.is_empty())
+        .map(|(label, histogram)| LatencyPercentiles {
+            count: histogram.len(),
+            min: histogram.min(),
+            max: histogram.max(),
+            mean: histogram.mean(),
+            percentiles: quantiles
+                .iter()
+                .map(|p| (format!("p{}", p), histogram.value_at_quantile(p / 100.0)))
+                .collect(),
+            label,
+        })
+        .collect();
+
+    json_with_etag(&headers, &results)
+}
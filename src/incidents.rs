@@ -0,0 +1,110 @@
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::admin::require_admin;
+use crate::models::RpcEndpoint;
+use crate::netcheck::{resolve_endpoint, traceroute};
+use crate::state::AppState;
+use crate::status::{EndpointState, Transition};
+use crate::storage::{scan_prefix, Store, INCIDENTS_PREFIX};
+
+/// An endpoint state transition, with the network path captured when it got worse
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IncidentRecord {
+    /// Unix milliseconds; records from before the switch hold float seconds under "timestamp"
+    #[serde(alias = "timestamp", deserialize_with = "crate::timestamps::deserialize_millis")]
+    pub timestamp_ms: i64,
+    pub nickname: String,
+    pub from: String,
+    pub to: String,
+    pub reason: Option<String>,
+    /// traceroute output from the moment of failure, if capture is enabled
+    pub path: Option<String>,
+}
+
+fn state_name(state: EndpointState) -> String {
+    format!("{:?}", state).to_lowercase()
+}
+
+fn incident_key(record: &IncidentRecord) -> String {
+    format!("{}{}:{}", INCIDENTS_PREFIX, record.nickname, record.timestamp_ms)
+}
+
+fn store_incident(db: &Store, record: &IncidentRecord) -> Result<(), Box<dyn std::error::Error>> {
+    db.put(incident_key(record).as_bytes(), serde_json::to_vec(record)?)?;
+    Ok(())
+}
+
+// Trace the path to the endpoint's host, resolving it the same way the probes do
+async fn capture_path(endpoint: &RpcEndpoint, max_hops: u8) -> String {
+    let address = match resolve_endpoint(endpoint).await {
+        Ok(address) => address,
+        Err(e) => return e,
+    };
+    traceroute(address.ip(), max_hops).await.unwrap_or_else(|e| e)
+}
+
+// Store an incident per transition; transitions to a worse state optionally get a
+// traceroute attached once it finishes
+pub fn record_transitions(db: &Arc<Store>, transitions: Vec<Transition>, endpoints: &[RpcEndpoint], traceroute_hops: Option<u8>) {
+    for transition in transitions {
+        let record = IncidentRecord {
+            timestamp_ms: transition.timestamp_ms,
+            nickname: transition.label.clone(),
+            from: state_name(transition.from),
+            to: state_name(transition.to),
+            reason: transition.reason,
+            path: None,
+        };
+        if let Err(e) = store_incident(db, &record) {
+            log_error!("Failed to store incident for {}: {}", record.nickname, e);
+            continue;
+        }
+
+        let endpoint = endpoints.iter().find(|e| e.nickname == transition.label);
+        if let (Some(max_hops), Some(endpoint), true) = (traceroute_hops, endpoint, transition.to > transition.from) {
+            let db = Arc::clone(db);
+            let endpoint = endpoint.clone();
+            tokio::spawn(async move {
+                let path = capture_path(&endpoint, max_hops).await;
+                let record = IncidentRecord { path: Some(path), ..record };
+                if let Err(e) = store_incident(&db, &record) {
+                    log_error!("Failed to store traceroute for {}: {}", record.nickname, e);
+                }
+            });
+        }
+    }
+}
+
+// State transitions with captured paths, newest first. Optional `rpc` (nickname) and `limit` params.
+pub async fn get_incidents(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<IncidentRecord>>, StatusCode> {
+    require_admin(&state, &headers)?;
+
+    let prefix = match params.get("rpc") {
+        Some(nickname) => format!("{}{}:", INCIDENTS_PREFIX, nickname),
+        None => INCIDENTS_PREFIX.to_string(),
+    };
+    let limit = params
+        .get("limit")
+        .and_then(|l| l.parse::<usize>().ok())
+        .unwrap_or(100);
+
+    let mut records: Vec<IncidentRecord> = scan_prefix::<IncidentRecord>(&state.db, &prefix)
+        .into_iter()
+        .map(|(_, record)| record)
+        .collect();
+    records.sort_by(|a, b| b.timestamp_ms.cmp(&a.timestamp_ms));
+    records.truncate(limit);
+
+    Ok(Json(records))
+}
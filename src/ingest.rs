@@ -0,0 +1,85 @@
+use axum::{extract::State, http::HeaderMap, http::StatusCode, Json};
+use serde::Deserialize;
+
+use crate::auth::bearer_matches;
+use crate::config::RetentionConfig;
+use crate::models::{EndpointKind, RPCResponse};
+use crate::rpc::store_response;
+use crate::state::AppState;
+
+const MAX_CLOCK_SKEW_MS: i64 = 60_000;
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum IngestPayload {
+    One(RPCResponse),
+    Many(Vec<RPCResponse>),
+}
+
+// Reject samples that would corrupt the key layout or fall outside the retention window. Late
+// samples are still accepted and rolled up, as long as they are within the 1-hour rollup horizon.
+pub fn validate_sample(sample: &RPCResponse, retention: &RetentionConfig) -> Result<(), String> {
+    let labels = [Some(&sample.nickname), sample.region.as_ref(), sample.source.as_ref()];
+    for label in labels.into_iter().flatten() {
+        // A leading '!' would land the sample in an internal keyspace such as "!paused:"
+        if label.is_empty() || label.starts_with('!') || label.contains(':') || label.contains('@') {
+            return Err(format!("Invalid label {:?}: must be non-empty, not start with '!' and without ':' or '@'", label));
+        }
+    }
+
+    if sample.round.is_some() {
+        return Err("round is assigned by the monitor".to_string());
+    }
+
+    // Jito samples carry no chain state
+    if sample.kind == EndpointKind::Rpc && sample.blockhash.is_empty() {
+        return Err("blockhash must not be empty".to_string());
+    }
+
+    let now = chrono::Utc::now().timestamp_millis();
+    let max_age_ms = retention.hour_secs() * 1000;
+    if sample.timestamp_ms > now + MAX_CLOCK_SKEW_MS || sample.timestamp_ms < now - max_age_ms {
+        return Err(format!("timestamp {} is outside the accepted window", sample.timestamp_ms));
+    }
+
+    Ok(())
+}
+
+fn authorized(state: &AppState, headers: &HeaderMap) -> Option<bool> {
+    let ingest = state.config.ingest.as_ref()?;
+    Some(ingest.tokens.iter().any(|token| bearer_matches(headers, token)))
+}
+
+// Push ingestion for external probes and CI jobs
+pub async fn ingest_samples(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<IngestPayload>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    match authorized(&state, &headers) {
+        None => return Err((StatusCode::NOT_FOUND, "Ingestion is disabled".to_string())),
+        Some(false) => return Err((StatusCode::UNAUTHORIZED, "Invalid token".to_string())),
+        Some(true) => {}
+    }
+
+    let samples = match payload {
+        IngestPayload::One(sample) => vec![sample],
+        IngestPayload::Many(samples) => samples,
+    };
+
+    for sample in &samples {
+        if sample.source.is_none() {
+            return Err((StatusCode::BAD_REQUEST, "source is required".to_string()));
+        }
+        validate_sample(sample, &state.config.retention).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
+
+    for sample in &samples {
+        store_response(&state.db, sample).map_err(|e| {
+            log_error!("Error storing ingested sample: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to store sample".to_string())
+        })?;
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
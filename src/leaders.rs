@@ -0,0 +1,311 @@
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use rocksdb::{Direction, IteratorMode};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::access::{allowed, resolve_scope};
+use crate::check::sample_errors;
+use crate::config::LeadersConfig;
+use crate::models::{EndpointKind, RPCResponse, RpcEndpoint};
+use crate::pause;
+use crate::rpc::{call_method, CallError};
+use crate::state::AppState;
+use crate::storage::{Store, LEADER_ROUNDS_PREFIX};
+use crate::timestamps::parse_time;
+use crate::usage;
+
+/// A poll round annotated with the leader of the highest slot any endpoint reported
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LeaderRound {
+    pub timestamp: f64,
+    pub slot: u64,
+    pub leader: String,
+    /// From `[leaders] regions`, if the leader is listed there
+    pub region: Option<String>,
+    /// Latency of each endpoint with a successful sample this round
+    pub latency_ms: BTreeMap<String, u128>,
+    /// Slots each of those endpoints was behind the round's highest slot
+    pub slot_lag: BTreeMap<String, u64>,
+}
+
+// The current epoch's schedule, with each slot's leader stored as an index into `identities`
+struct Schedule {
+    epoch: u64,
+    first_slot: u64,
+    identities: Vec<String>,
+    slot_leaders: Vec<u32>,
+}
+
+impl Schedule {
+    fn leader(&self, slot: u64) -> Option<&str> {
+        let index = slot.checked_sub(self.first_slot)? as usize;
+        let leader = *self.slot_leaders.get(index)?;
+        self.identities.get(leader as usize).map(String::as_str)
+    }
+}
+
+struct Leaders {
+    config: LeadersConfig,
+    schedule: Option<Schedule>,
+}
+
+// None while leader tracking is disabled
+static LEADERS: Lazy<Mutex<Option<Leaders>>> = Lazy::new(|| Mutex::new(None));
+
+pub fn enable(config: LeadersConfig) {
+    *LEADERS.lock().unwrap() = Some(Leaders { config, schedule: None });
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EpochInfo {
+    epoch: u64,
+    absolute_slot: u64,
+    slot_index: u64,
+    slots_in_epoch: u64,
+}
+
+// Load the schedule of the endpoint's current epoch unless it is already cached
+async fn refresh(endpoint: &RpcEndpoint) -> Result<(), CallError> {
+    let (info, _) = call_method::<EpochInfo>(endpoint, "getEpochInfo", None).await?;
+    let cached_epoch = LEADERS
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|leaders| leaders.schedule.as_ref().map(|schedule| schedule.epoch));
+    if cached_epoch == Some(info.epoch) {
+        return Ok(());
+    }
+
+    let first_slot = info.absolute_slot - info.slot_index;
+    let (schedule, _) =
+        call_method::<Option<HashMap<String, Vec<u64>>>>(endpoint, "getLeaderSchedule", Some(json!([first_slot]))).await?;
+    let schedule = schedule
+        .ok_or_else(|| CallError::new("no leader schedule", format!("no leader schedule for epoch {}", info.epoch)))?;
+
+    let mut identities = Vec::with_capacity(schedule.len());
+    let mut slot_leaders = vec![u32::MAX; info.slots_in_epoch as usize];
+    for (index, (identity, slots)) in schedule.into_iter().enumerate() {
+        for slot in slots {
+            if let Some(leader) = slot_leaders.get_mut(slot as usize) {
+                *leader = index as u32;
+            }
+        }
+        identities.push(identity);
+    }
+    log_info!("📅 Loaded the leader schedule for epoch {} ({} leaders)", info.epoch, identities.len());
+
+    if let Some(leaders) = LEADERS.lock().unwrap().as_mut() {
+        leaders.schedule = Some(Schedule {
+            epoch: info.epoch,
+            first_slot,
+            identities,
+            slot_leaders,
+        });
+    }
+    Ok(())
+}
+
+// Check for a new epoch every 30 seconds, asking the first endpoint that answers
+pub fn spawn_schedule_refresh(endpoints: Vec<RpcEndpoint>) {
+    tokio::spawn(async move {
+        loop {
+            for endpoint in endpoints.iter().filter(|e| !pause::is_paused(&e.nickname) && usage::allow_probe(e)) {
+                match refresh(endpoint).await {
+                    Ok(()) => break,
+                    Err(e) => log_error!("[{}] Leader schedule refresh failed: {}", endpoint.nickname, e),
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(30)).await;
+        }
+    });
+}
+
+// Rounds are keyed as "!leader:<ts_millis>" so a time window is a contiguous key range
+fn round_key(timestamp: f64) -> String {
+    format!("{}{}", LEADER_ROUNDS_PREFIX, (timestamp * 1000.0) as i64)
+}
+
+/// Store the round's leader together with each endpoint's latency and slot lag
+pub fn record_round(db: &Store, samples: &[RPCResponse]) {
+    let ok: Vec<&RPCResponse> = samples
+        .iter()
+        .filter(|sample| sample.region.is_none() && sample.kind == EndpointKind::Rpc && sample_errors(sample).is_empty())
+        .collect();
+    let tip = match ok.iter().map(|sample| sample.slot).max() {
+        Some(tip) => tip,
+        None => return,
+    };
+
+    let (leader, region) = {
+        let guard = LEADERS.lock().unwrap();
+        let leaders = match guard.as_ref() {
+            Some(leaders) => leaders,
+            None => return,
+        };
+        match leaders.schedule.as_ref().and_then(|schedule| schedule.leader(tip)) {
+            Some(leader) => (leader.to_string(), leaders.config.regions.get(leader).cloned()),
+            None => return,
+        }
+    };
+
+    let round = LeaderRound {
+        timestamp: ok.iter().map(|sample| sample.timestamp_secs()).fold(0.0, f64::max),
+        slot: tip,
+        leader,
+        region,
+        latency_ms: ok.iter().map(|sample| (sample.nickname.clone(), sample.latency_ms)).collect(),
+        slot_lag: ok.iter().map(|sample| (sample.nickname.clone(), tip - sample.slot)).collect(),
+    };
+    let result = serde_json::to_vec(&round)
+        .map_err(|e| e.to_string())
+        .and_then(|body| db.put(round_key(round.timestamp).as_bytes(), body).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        log_error!("Error storing leader round: {}", e);
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct LeaderCorrelation {
+    /// Leader identity, or region when grouped by region
+    pub name: String,
+    pub rounds: u64,
+    pub avg_latency_ms: f64,
+    pub avg_slot_lag: f64,
+    /// Average latency relative to the whole window's; a high ratio on every endpoint
+    /// points at the leader rather than at the endpoints
+    pub latency_ratio: f64,
+    pub slot_lag_ratio: f64,
+    /// Average latency of each endpoint while this leader (or region) was producing
+    pub endpoints: BTreeMap<String, f64>,
+}
+
+#[derive(Default)]
+struct Totals {
+    rounds: u64,
+    latency_sum: f64,
+    slot_lag_sum: f64,
+    measurements: u64,
+    endpoints: BTreeMap<String, (f64, u64)>,
+}
+
+impl Totals {
+    fn add(&mut self, round: &LeaderRound, visible: &dyn Fn(&str) -> bool) {
+        let mut counted = false;
+        for (nickname, latency) in round.latency_ms.iter().filter(|(nickname, _)| visible(nickname.as_str())) {
+            let lag = round.slot_lag.get(nickname).copied().unwrap_or(0);
+            self.latency_sum += *latency as f64;
+            self.slot_lag_sum += lag as f64;
+            self.measurements += 1;
+            let endpoint = self.endpoints.entry(nickname.clone()).or_default();
+            endpoint.0 += *latency as f64;
+            endpoint.1 += 1;
+            counted = true;
+        }
+        if counted {
+            self.rounds += 1;
+        }
+    }
+
+    fn avg_latency(&self) -> f64 {
+        self.latency_sum / self.measurements.max(1) as f64
+    }
+
+    fn avg_slot_lag(&self) -> f64 {
+        self.slot_lag_sum / self.measurements.max(1) as f64
+    }
+}
+
+// Latency and slot lag per leader, ranked by how much worse they were than the window as a
+// whole. Params: `from`/`to` (unix seconds, millis or RFC 3339, default: the last hour), `rpc` (nickname),
+// `group` ("leader" or "region"), `min_rounds` (default 1) and `limit`.
+pub async fn get_leaders(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<LeaderCorrelation>>, StatusCode> {
+    let scope = resolve_scope(&state, &headers)?;
+
+    let now = Utc::now().timestamp();
+    let from = params
+        .get("from")
+        .and_then(|ts| parse_time(ts))
+        .unwrap_or(now - 3600);
+    let to = params.get("to").and_then(|ts| parse_time(ts)).unwrap_or(now);
+    let by_region = params.get("group").map(|group| group == "region").unwrap_or(false);
+    let min_rounds = params
+        .get("min_rounds")
+        .and_then(|n| n.parse::<u64>().ok())
+        .unwrap_or(1);
+    let limit = params
+        .get("limit")
+        .and_then(|l| l.parse::<usize>().ok())
+        .unwrap_or(100);
+
+    let visible = |nickname: &str| {
+        params.get("rpc").map_or(true, |rpc| rpc == nickname)
+            && state
+                .config
+                .rpc
+                .endpoints
+                .iter()
+                .find(|e| e.nickname == nickname)
+                .map_or(scope.is_none(), |e| allowed(&scope, &e.nickname, &e.tags))
+    };
+
+    let mut overall = Totals::default();
+    let mut groups: HashMap<String, Totals> = HashMap::new();
+    let start = format!("{}{}", LEADER_ROUNDS_PREFIX, from * 1000);
+    let rounds = state
+        .db
+        .iterator(IteratorMode::From(start.as_bytes(), Direction::Forward))
+        .filter_map(|item| item.ok())
+        .take_while(|(key, _)| key.starts_with(LEADER_ROUNDS_PREFIX.as_bytes()))
+        .filter_map(|(_, value)| serde_json::from_slice::<LeaderRound>(&value).ok())
+        .take_while(|round| round.timestamp <= to as f64);
+    for round in rounds {
+        let name = if by_region {
+            round.region.clone().unwrap_or_else(|| "unknown".to_string())
+        } else {
+            round.leader.clone()
+        };
+        overall.add(&round, &visible);
+        groups.entry(name).or_default().add(&round, &visible);
+    }
+
+    let (overall_latency, overall_lag) = (overall.avg_latency(), overall.avg_slot_lag());
+    let mut results: Vec<LeaderCorrelation> = groups
+        .into_iter()
+        .filter(|(_, totals)| totals.rounds >= min_rounds)
+        .map(|(name, totals)| LeaderCorrelation {
+            rounds: totals.rounds,
+            avg_latency_ms: totals.avg_latency(),
+            avg_slot_lag: totals.avg_slot_lag(),
+            latency_ratio: if overall_latency > 0.0 { totals.avg_latency() / overall_latency } else { 0.0 },
+            slot_lag_ratio: if overall_lag > 0.0 { totals.avg_slot_lag() / overall_lag } else { 0.0 },
+            endpoints: totals
+                .endpoints
+                .iter()
+                .map(|(nickname, (sum, count))| (nickname.clone(), sum / *count as f64))
+                .collect(),
+            name,
+        })
+        .collect();
+    results.sort_by(|a, b| {
+        b.latency_ratio
+            .partial_cmp(&a.latency_ratio)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    results.truncate(limit);
+
+    Ok(Json(results))
+}
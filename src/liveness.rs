@@ -0,0 +1,91 @@
+use axum::{extract::State, http::StatusCode};
+use once_cell::sync::Lazy;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::time::Duration;
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::state::AppState;
+
+// Unix seconds the poller last completed a round that made progress (0 = never)
+static LAST_PROGRESS: AtomicI64 = AtomicI64::new(0);
+
+// Unix seconds the poller started (0 = not yet)
+static STARTED: AtomicI64 = AtomicI64::new(0);
+
+// Set once a poll round produced at least one healthy sample
+static READY: AtomicBool = AtomicBool::new(false);
+
+// Set on shutdown; no new rounds start and readiness reports false
+static DRAINING: AtomicBool = AtomicBool::new(false);
+
+// Held by the poller for the whole of a round, so a drain can wait for it to finish
+static ROUND: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+fn mark_progress() {
+    LAST_PROGRESS.store(chrono::Utc::now().timestamp(), Ordering::Relaxed);
+}
+
+pub fn mark_started() {
+    STARTED.store(chrono::Utc::now().timestamp(), Ordering::Relaxed);
+}
+
+/// Seconds since the poller last completed a round, or None if it never has
+pub fn seconds_since_progress() -> Option<i64> {
+    match LAST_PROGRESS.load(Ordering::Relaxed) {
+        0 => None,
+        ts => Some(chrono::Utc::now().timestamp() - ts),
+    }
+}
+
+/// Start a poll round; None once draining, in which case the poller should stop
+pub async fn begin_round() -> Option<MutexGuard<'static, ()>> {
+    let guard = ROUND.lock().await;
+    if DRAINING.load(Ordering::SeqCst) {
+        return None;
+    }
+    Some(guard)
+}
+
+/// Called when a round's samples are stored. A round counts as progress if it stored at least
+/// one sample, failed or not, or if every endpoint was skipped (paused, over budget or behind an
+/// open breaker); the first round with a healthy sample makes us ready
+pub fn finish_round(progress: bool, healthy: bool) {
+    if progress {
+        mark_progress();
+    }
+    if healthy && !READY.swap(true, Ordering::SeqCst) {
+        log_info!("✅ First poll round stored, reporting ready");
+    }
+}
+
+/// Stop starting rounds and wait (up to `timeout`) for the one in flight to be stored.
+/// Returns false if the round was still running when the timeout passed.
+pub async fn drain(timeout: Duration) -> bool {
+    DRAINING.store(true, Ordering::SeqCst);
+    log_info!("🛑 Draining: finishing the poll round in flight");
+    tokio::time::timeout(timeout, ROUND.lock()).await.is_ok()
+}
+
+// Liveness: 200 while the poller keeps completing rounds (or is still within its first
+// `liveness_timeout_secs`), 503 once it has stalled. Failing upstreams don't count as a stall.
+pub async fn get_healthz(State(state): State<AppState>) -> (StatusCode, String) {
+    let max_age = state.config.server.liveness_timeout_secs() as i64;
+    let since_start = chrono::Utc::now().timestamp() - STARTED.load(Ordering::Relaxed);
+    match seconds_since_progress() {
+        Some(age) if age <= max_age => (StatusCode::OK, format!("ok, last round {}s ago", age)),
+        None if since_start <= max_age => (StatusCode::OK, "ok, starting".to_string()),
+        Some(age) => (StatusCode::SERVICE_UNAVAILABLE, format!("stalled, last round {}s ago", age)),
+        None => (StatusCode::SERVICE_UNAVAILABLE, "stalled, no round completed".to_string()),
+    }
+}
+
+// Readiness: 200 once a poll round has stored data, 503 before that and while draining
+pub async fn get_readyz() -> (StatusCode, &'static str) {
+    if DRAINING.load(Ordering::SeqCst) {
+        (StatusCode::SERVICE_UNAVAILABLE, "draining")
+    } else if READY.load(Ordering::SeqCst) {
+        (StatusCode::OK, "ready")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "waiting for the first poll round")
+    }
+}
@@ -0,0 +1,119 @@
+use chrono::{Local, NaiveDate};
+use once_cell::sync::OnceCell;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::config::{LoggingConfig, StorageConfig};
+
+// Log to stdout/stderr as before, and to the rotating log file if one is configured
+macro_rules! log_info {
+    ($($arg:tt)*) => {{
+        let message = format!($($arg)*);
+        println!("{}", message);
+        $crate::logging::write_line("INFO", &message);
+    }};
+}
+
+macro_rules! log_error {
+    ($($arg:tt)*) => {{
+        let message = format!($($arg)*);
+        eprintln!("{}", message);
+        $crate::logging::write_line("ERROR", &message);
+    }};
+}
+
+static LOG_FILE: OnceCell<Mutex<RotatingFile>> = OnceCell::new();
+
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    opened_on: NaiveDate,
+    max_bytes: u64,
+    daily: bool,
+    keep: usize,
+}
+
+impl RotatingFile {
+    fn open(path: &Path) -> io::Result<(File, u64)> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        Ok((file, size))
+    }
+
+    fn needs_rotation(&self, today: NaiveDate) -> bool {
+        (self.max_bytes > 0 && self.size >= self.max_bytes) || (self.daily && today != self.opened_on)
+    }
+
+    // monitor.log -> monitor.log.1 -> monitor.log.2 ..., dropping anything past `keep`
+    fn rotate(&mut self, today: NaiveDate) -> io::Result<()> {
+        let numbered = |n: usize| PathBuf::from(format!("{}.{}", self.path.display(), n));
+
+        let _ = fs::remove_file(numbered(self.keep));
+        for n in (1..self.keep).rev() {
+            let from = numbered(n);
+            if from.exists() {
+                fs::rename(&from, numbered(n + 1))?;
+            }
+        }
+        if self.keep > 0 {
+            fs::rename(&self.path, numbered(1))?;
+        } else {
+            fs::remove_file(&self.path)?;
+        }
+
+        let (file, size) = Self::open(&self.path)?;
+        self.file = file;
+        self.size = size;
+        self.opened_on = today;
+        Ok(())
+    }
+
+    fn write_line(&mut self, level: &str, message: &str) -> io::Result<()> {
+        let now = Local::now();
+        if self.needs_rotation(now.date_naive()) {
+            self.rotate(now.date_naive())?;
+        }
+
+        let line = format!("{} {:<5} {}\n", now.to_rfc3339(), level, message);
+        self.file.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+}
+
+pub fn init(config: &LoggingConfig, storage: &StorageConfig) -> io::Result<()> {
+    let path = match &config.file {
+        Some(file) => storage.resolve(file),
+        None => return Ok(()),
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let (file, size) = RotatingFile::open(&path)?;
+    let rotating = RotatingFile {
+        path,
+        file,
+        size,
+        opened_on: Local::now().date_naive(),
+        max_bytes: config.max_size_mb.unwrap_or(100) * 1024 * 1024,
+        daily: config.rotate_daily.unwrap_or(false),
+        keep: config.max_files.unwrap_or(5),
+    };
+
+    LOG_FILE
+        .set(Mutex::new(rotating))
+        .map_err(|_| io::Error::other("log file already initialized"))
+}
+
+pub fn write_line(level: &str, message: &str) {
+    if let Some(file) = LOG_FILE.get() {
+        let mut file = file.lock().unwrap();
+        if let Err(e) = file.write_line(level, message) {
+            eprintln!("Error writing log file: {}", e);
+        }
+    }
+}
@@ -1,32 +1,113 @@
+#[macro_use]
+mod logging;
+
+mod access;
+mod admin;
+mod agent;
+mod alerts;
+mod anomaly;
+mod anonymize;
+mod assets;
+mod auth;
+mod backup;
+mod badge;
+mod bench;
+mod breaker;
+mod check;
+mod cluster;
+mod compare;
 mod config;
+mod confirmation;
+mod discovery;
+mod endpoints;
+mod etag;
+mod histograms;
+mod incidents;
+mod ingest;
+mod leaders;
+mod liveness;
 mod metrics;
 mod models;
+mod netcheck;
+mod pause;
+mod probes;
+mod protocol_stats;
+mod quic;
+mod reference;
+mod reports;
+mod retention;
+mod rounds;
 mod rpc;
+mod state;
+mod stats;
+mod status;
+mod storage;
+mod subscribe;
+mod systemd;
+mod tags;
+mod timestamps;
+mod usage;
+mod validate;
+mod websocket;
 
 use axum::{
+    middleware,
     response::Redirect,
-    routing::{get, get_service},
+    routing::{get, get_service, post},
     Router,
 };
-use chrono::{Duration, Utc};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use futures::future::join_all;
 use rocksdb::{Options, DB};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::task;
+use tower_http::compression::CompressionLayer;
 use tower_http::services::ServeDir;
 
-use crate::config::load_config;
-use crate::config::AppConfig;
+use crate::admin::get_errors;
+use crate::agent::{receive_agent_samples, run_agent};
+use crate::alerts::{get_alerts, get_open_alerts, spawn_alerts};
+use crate::anomaly::get_anomalies;
+use crate::assets::serve_asset;
+use crate::backup::post_backup;
+use crate::badge::get_badge;
+use crate::bench::{run_bench, BenchOptions};
+use crate::check::{run_check, sample_errors, CheckThresholds};
+use crate::cluster::get_cluster_events;
+use crate::compare::{parse_duration, run_compare, CompareOptions};
+use crate::config::{config_path, load_config};
+use crate::config::{AppConfig, StorageConfig};
+use crate::confirmation::{get_confirmations, spawn_confirmation_probe};
+use crate::endpoints::{get_endpoint, get_endpoint_stats};
+use crate::histograms::get_percentiles;
+use crate::incidents::get_incidents;
+use crate::liveness::{get_healthz, get_readyz};
+use crate::ingest::ingest_samples;
+use crate::leaders::get_leaders;
 use crate::metrics::get_metrics;
-use crate::models::RPCResponse;
-use crate::rpc::fetch_blockhash_and_slot;
+use crate::models::{EndpointKind, RPCResponse, RpcEndpoint};
+use crate::pause::{post_pause, post_resume};
+use crate::probes::{get_heavy, get_probes, spawn_probes};
+use crate::retention::run_retention;
+use crate::rounds::{get_delta, get_round};
+use crate::rpc::{fetch_blockhash_and_slot, migrate_sample_keys, store_response};
+use crate::state::AppState;
+use crate::status::get_status;
+use crate::storage::{MemoryStore, Store};
+use crate::usage::get_usage;
 
 /// CLI arguments
 #[derive(Parser)]
 #[command(name = "SVS RPC Monitor", about = "Solana RPC Performance Monitor")]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Config file (default: $SVS_MONITOR_CONFIG, then the XDG config directory, then ./config.toml)
+    #[arg(long, global = true)]
+    config: Option<String>,
+
     /// IP address to bind the server to
     #[arg(long)]
     listen_ip: Option<String>,
@@ -34,105 +115,457 @@ struct Cli {
     /// Port to bind the server to
     #[arg(long)]
     port: Option<u16>,
+
+    /// Run only the prober and ship results to the aggregator in `[agent]`
+    #[arg(long)]
+    agent: bool,
+
+    /// Collect and store metrics without starting the web server
+    #[arg(long)]
+    headless: bool,
+
+    /// Base directory for the database and other generated files
+    #[arg(long)]
+    data_dir: Option<String>,
+
+    /// RocksDB path (relative to the data directory unless absolute)
+    #[arg(long)]
+    db_path: Option<String>,
+
+    /// Serve the dashboard from this directory instead of the embedded assets
+    #[arg(long)]
+    static_dir: Option<String>,
+
+    /// Replace the database with this checkpoint before starting (the old one is kept aside)
+    #[arg(long)]
+    restore: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Probe all configured endpoints once, print a report and exit
+    Check {
+        /// Print results as JSON instead of a table
+        #[arg(long)]
+        json: bool,
+
+        /// Fail endpoints slower than this
+        #[arg(long, default_value_t = 1000)]
+        max_latency_ms: u64,
+
+        /// Fail endpoints this many slots behind the highest observed slot
+        #[arg(long, default_value_t = 25)]
+        max_slot_lag: u64,
+    },
+    /// Run a burst of requests against one endpoint and report latency percentiles
+    Bench {
+        /// Nickname of the endpoint to benchmark
+        #[arg(long)]
+        endpoint: String,
+
+        /// Total number of requests to send
+        #[arg(long, default_value_t = 500)]
+        requests: usize,
+
+        /// Number of requests in flight at once
+        #[arg(long, default_value_t = 10)]
+        concurrency: usize,
+
+        /// JSON-RPC method to call
+        #[arg(long, default_value = "getSlot")]
+        method: String,
+
+        /// JSON-encoded params array for the method
+        #[arg(long)]
+        params: Option<String>,
+    },
+    /// Probe two endpoints side by side and print a statistical comparison
+    Compare {
+        /// Nickname of the first endpoint
+        a: String,
+
+        /// Nickname of the second endpoint
+        b: String,
+
+        /// How long to run, e.g. "90s", "5m", "1h"
+        #[arg(long, default_value = "5m", value_parser = parse_duration)]
+        duration: std::time::Duration,
+
+        /// Pause between probe rounds
+        #[arg(long, default_value = "2s", value_parser = parse_duration)]
+        interval: std::time::Duration,
+    },
+    /// Check the config file for mistakes and exit non-zero if any are found
+    Validate {
+        /// Also send each endpoint one round of requests to check it is reachable
+        #[arg(long)]
+        probe: bool,
+    },
 }
 
-fn setup_db() -> Arc<DB> {
+fn setup_db(storage: &StorageConfig) -> Arc<Store> {
+    if storage.in_memory() {
+        return Arc::new(Store::Memory(MemoryStore::new(storage.max_samples())));
+    }
     let mut opts = Options::default();
     opts.create_if_missing(true);
     opts.set_write_buffer_size(64 * 1024 * 1024);
     opts.set_compression_type(rocksdb::DBCompressionType::Lz4);
-    Arc::new(DB::open(&opts, "rpc_metrics.db").expect("Failed to open database"))
+    Arc::new(Store::Disk(DB::open(&opts, storage.db_path()).expect("Failed to open database")))
 }
 
-async fn cleanup_old_entries(db: Arc<DB>) -> Result<(), Box<dyn std::error::Error>> {
-    let one_hour_ago_ts = (Utc::now() - Duration::hours(1)).timestamp();
-    let mut batch = rocksdb::WriteBatch::default();
-
-    for item in db.iterator(rocksdb::IteratorMode::Start) {
-        if let Ok((key, value)) = item {
-            if let Ok(response) = serde_json::from_slice::<RPCResponse>(&value) {
-                if response.timestamp < one_hour_ago_ts as f64 {
-                    batch.delete(key);
-                }
-            }
+// Ctrl+C, or SIGTERM as sent by Kubernetes and systemd on stop
+async fn shutdown_signal() {
+    let mut terminate = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+        Ok(terminate) => terminate,
+        Err(e) => {
+            log_error!("Error listening for SIGTERM: {}", e);
+            let _ = tokio::signal::ctrl_c().await;
+            return;
         }
+    };
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = terminate.recv() => {}
     }
+}
 
-    db.write(batch)?;
-    Ok(())
+// Endpoints that serve Solana JSON-RPC, leaving out Jito block engines
+fn rpc_endpoints(config: &AppConfig) -> Vec<RpcEndpoint> {
+    config
+        .rpc
+        .endpoints
+        .iter()
+        .filter(|e| e.kind == EndpointKind::Rpc)
+        .cloned()
+        .collect()
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Cli::parse();
-    let db = setup_db();
-    let mut config: AppConfig = load_config()?;
+    // Validation reports a broken config itself, so it runs before the config is loaded
+    if let Some(Command::Validate { probe }) = args.command {
+        std::process::exit(validate::run_validate(args.config.as_deref(), probe).await);
+    }
+    let mut config: AppConfig = load_config(&config_path(args.config.as_deref())?)?;
+
+    match args.command {
+        Some(Command::Check { json, max_latency_ms, max_slot_lag }) => {
+            let thresholds = CheckThresholds {
+                max_latency_ms: max_latency_ms as u128,
+                max_slot_lag,
+            };
+            let passed = run_check(&config.rpc.endpoints, thresholds, json).await?;
+            std::process::exit(if passed { 0 } else { 1 });
+        }
+        Some(Command::Bench { endpoint, requests, concurrency, method, params }) => {
+            let endpoint = config
+                .rpc
+                .endpoints
+                .iter()
+                .find(|e| e.nickname == endpoint)
+                .ok_or_else(|| format!("No endpoint named {} in config.toml", endpoint))?;
+            let params: Option<serde_json::Value> = params.map(|p| serde_json::from_str(&p)).transpose()?;
+            let options = BenchOptions {
+                method,
+                params,
+                requests,
+                concurrency,
+            };
+            return run_bench(endpoint, options).await;
+        }
+        Some(Command::Compare { a, b, duration, interval }) => {
+            let find = |nickname: &str| {
+                config
+                    .rpc
+                    .endpoints
+                    .iter()
+                    .find(|e| e.nickname == nickname)
+                    .ok_or_else(|| format!("No endpoint named {} in config.toml", nickname))
+            };
+            let (a, b) = (find(a.as_str())?, find(b.as_str())?);
+            return run_compare(a, b, CompareOptions { duration, interval }).await;
+        }
+        Some(Command::Validate { .. }) | None => {}
+    }
 
     // ✅ Override TOML config with CLI arguments
     if let Some(ip) = args.listen_ip {
         config.server.listen_ip = Some(ip);
     }
+    if let Some(dir) = args.data_dir {
+        config.storage.data_dir = Some(dir);
+    }
+    if let Some(path) = args.db_path {
+        config.storage.path = Some(path);
+    }
+    if let Some(dir) = args.static_dir {
+        config.server.static_dir = Some(dir);
+    }
     if let Some(port) = args.port {
         config.server.port = Some(port);
     }
+    if args.headless {
+        config.server.headless = Some(true);
+    }
+    let headless = config.server.headless.unwrap_or(false);
+    timestamps::set_display_timezone(config.server.timezone.as_deref().unwrap_or("UTC"))?;
 
-    std::fs::create_dir_all("static")?;
-    std::fs::write("static/index.html", include_str!("static/index.html"))?;
-    std::fs::write("static/dashboard.js", include_str!("static/dashboard.js"))?;
-    std::fs::write("static/darkMode.js", include_str!("static/darkMode.js"))?;
-    std::fs::write("static/styles.css", include_str!("static/styles.css"))?;
-    std::fs::write("static/logo.svg", include_str!("static/logo.svg"))?;
+    // Memory mode writes nothing to disk, not even the data directory or log file
+    if config.storage.in_memory() {
+        if config.logging.file.is_some() {
+            log_info!("🧠 In-memory storage: [logging] file is ignored, logging to stdout/stderr only");
+        }
+    } else {
+        if let Some(dir) = &config.storage.data_dir {
+            std::fs::create_dir_all(dir)?;
+        }
+        logging::init(&config.logging, &config.storage)?;
+    }
+
+    if args.agent {
+        let agent = config
+            .agent
+            .clone()
+            .ok_or("--agent requires an [agent] section in config.toml")?;
+        return run_agent(agent, config.rpc.endpoints.clone()).await;
+    }
+
+    if let Some(discovery_config) = &config.discovery {
+        match discovery::discover(discovery_config, &config.rpc).await {
+            Ok(discovered) => config.rpc.endpoints.extend(discovered),
+            Err(e) => log_error!("Endpoint discovery failed: {}", e),
+        }
+    }
+
+    if let Some(checkpoint) = &args.restore {
+        if config.storage.in_memory() {
+            return Err("--restore needs [storage] mode = \"disk\"".into());
+        }
+        backup::restore(std::path::Path::new(checkpoint), &config.storage.db_path())?;
+    }
+
+    let db = setup_db(&config.storage);
+    if config.storage.in_memory() {
+        log_info!(
+            "🧠 In-memory storage: nothing is written to disk, all data is lost on exit, and only the newest {} raw samples are kept",
+            config.storage.max_samples()
+        );
+    }
+    if let Err(e) = migrate_sample_keys(&db) {
+        log_error!("Error rewriting legacy sample keys: {}", e);
+    }
+    usage::load(&db);
+    anonymize::load(&db);
+    usage::spawn_flush(Arc::clone(&db), config.retention.hour_secs());
+    histograms::spawn_flush(Arc::clone(&db));
+    protocol_stats::load(&db);
+    rounds::load(&db);
+    pause::load(&db, &config.rpc.endpoints);
+    protocol_stats::spawn_flush(Arc::clone(&db));
+
+    if let Some(reports_config) = &config.reports {
+        if config.storage.in_memory() && reports_config.output_dir.is_some() {
+            log_info!("🧠 In-memory storage: [reports] output_dir is ignored, reports only go to the webhook");
+        }
+        reports::spawn_reports(
+            reports_config.schedule,
+            reports_config
+                .output_dir
+                .as_deref()
+                .filter(|_| !config.storage.in_memory())
+                .map(|dir| config.storage.resolve(dir)),
+            reports_config.webhook_url.clone(),
+        );
+    }
+
+    if let Some(confirmation_config) = &config.confirmation {
+        spawn_confirmation_probe(confirmation_config.clone(), rpc_endpoints(&config), Arc::clone(&db))?;
+    }
+
+    spawn_probes(&config.probes, config.rpc.endpoints.clone(), Arc::clone(&db));
+    spawn_alerts(config.alerts.clone(), Arc::clone(&db));
+    if let Some(anomaly_config) = &config.anomaly {
+        anomaly::enable(anomaly_config.clone());
+    }
+    if let Some(breaker_config) = &config.breaker {
+        breaker::enable(breaker_config.clone());
+    }
+    if let Some(cluster_config) = &config.cluster_events {
+        cluster::enable(cluster_config.clone());
+    }
+    if let Some(reference_config) = &config.cluster_reference {
+        reference::spawn_reference(reference_config.clone());
+    }
+    if let Some(leaders_config) = &config.leaders {
+        leaders::enable(leaders_config.clone());
+        leaders::spawn_schedule_refresh(rpc_endpoints(&config));
+    }
 
     let db_clone = Arc::clone(&db);
     let endpoints = config.rpc.endpoints.clone();
+    let status_config = config.status.clone();
+    liveness::mark_started();
     tokio::spawn(async move {
         loop {
+            // Holding the round open lets a shutdown wait until its samples are stored
+            let round_guard = match liveness::begin_round().await {
+                Some(guard) => guard,
+                None => break,
+            };
+            let round = rounds::next(&db_clone);
             let tasks: Vec<_> = endpoints
-                .clone()
-                .into_iter()
+                .iter()
+                .filter(|endpoint| !pause::is_paused(&endpoint.nickname) && usage::allow_probe(endpoint))
+                .cloned()
                 .map(|endpoint| {
                     let db = Arc::clone(&db_clone);
+                    // Whether the endpoint was actually probed, and its sample if it was stored
                     task::spawn(async move {
-                        if let Err(e) = fetch_blockhash_and_slot(endpoint, db).await {
-                            eprintln!("Error: {}", e);
+                        if !breaker::allow(&endpoint.nickname) {
+                            let sample = RPCResponse {
+                                round: Some(round),
+                                ..breaker::open_sample(&endpoint)
+                            };
+                            if let Err(e) = store_response(&db, &sample) {
+                                log_error!("Error: {}", e);
+                                return (false, None);
+                            }
+                            return (false, Some(sample));
+                        }
+                        match fetch_blockhash_and_slot(endpoint, db, round).await {
+                            Ok(sample) => (true, Some(sample)),
+                            Err(e) => {
+                                log_error!("Error: {}", e);
+                                (true, None)
+                            }
                         }
                     })
                 })
                 .collect();
 
-            join_all(tasks).await;
+            let results: Vec<(bool, Option<RPCResponse>)> = join_all(tasks)
+                .await
+                .into_iter()
+                .map(|result| result.unwrap_or((true, None)))
+                .collect();
+            let probed = results.iter().filter(|(probed, _)| *probed).count();
+            let samples: Vec<RPCResponse> = results.into_iter().filter_map(|(_, sample)| sample).collect();
+            rounds::record(&db_clone, round, &samples);
+            breaker::record_round(&samples);
+            reports::record_round(&samples);
+            histograms::record_round(&samples);
+            alerts::record_round(&samples);
+            anomaly::record_round(&db_clone, &samples);
+            leaders::record_round(&db_clone, &samples);
+            cluster::record_round(&db_clone, &samples);
+            let transitions = status::record_round(&samples, &status_config);
+            incidents::record_transitions(&db_clone, transitions, &endpoints, status_config.traceroute_hops());
+            // A round that probed endpoints but stored nothing means the database writer is stuck
+            liveness::finish_round(
+                !samples.is_empty() || probed == 0,
+                samples.iter().any(|sample| sample_errors(sample).is_empty()),
+            );
+            drop(round_guard);
             tokio::time::sleep(tokio::time::Duration::from_millis(2000)).await;
         }
     });
 
     let db_clone = Arc::clone(&db);
+    let retention = config.retention.clone();
     tokio::spawn(async move {
         loop {
-            if let Err(e) = cleanup_old_entries(db_clone.clone()).await {
-                eprintln!("Error cleaning up old entries: {}", e);
+            if let Err(e) = run_retention(db_clone.clone(), &retention).await {
+                log_error!("Error applying retention: {}", e);
             }
             tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
         }
     });
 
-    let app = Router::new()
-        .route("/", get(|| async { Redirect::to("/static/index.html") }))
-        .route("/api/metrics", get(get_metrics))
-        .nest_service("/static", get_service(ServeDir::new("static")))
-        .with_state(db);
+    systemd::spawn_watchdog();
+
+    let drain_timeout = std::time::Duration::from_secs(config.server.drain_timeout_secs());
+    let db_clone = Arc::clone(&db);
+    let shutdown = async move {
+        shutdown_signal().await;
+        systemd::notify("STOPPING=1");
+        if !liveness::drain(drain_timeout).await {
+            log_error!("Poll round still running after {}s, shutting down anyway", drain_timeout.as_secs());
+        }
+        if let Err(e) = db_clone.flush() {
+            log_error!("Error flushing the database: {}", e);
+        }
+    };
+
+    if headless {
+        log_info!("🛰️ Headless mode: collecting metrics without the web server (Ctrl+C to stop)");
+        systemd::notify("READY=1");
+        shutdown.await;
+        return Ok(());
+    }
 
     let ip = config
         .server
         .listen_ip
+        .clone()
         .unwrap_or_else(|| "127.0.0.1".to_string());
     let port = config.server.port.unwrap_or(3000);
     let addr_str = format!("{}:{}", ip, port);
 
     let addr: SocketAddr = addr_str.parse()?;
-    println!("🚀 Server running on http://{}", addr);
-    axum::Server::bind(&addr)
-        .serve(app.into_make_service())
-        .await?;
+
+    let state = AppState {
+        db,
+        config: Arc::new(config),
+    };
+
+    let static_dir = state.config.server.static_dir.clone();
+
+    let app = Router::new()
+        .route("/", get(|| async { Redirect::to("/static/index.html") }))
+        .route("/api/metrics", get(get_metrics))
+        .route("/api/metrics/delta", get(get_delta))
+        .route("/api/status", get(get_status))
+        .route("/badge/:file", get(get_badge))
+        .route("/api/confirmations", get(get_confirmations))
+        .route("/api/probes", get(get_probes))
+        .route("/api/heavy", get(get_heavy))
+        .route("/api/endpoints/:nickname", get(get_endpoint))
+        .route("/api/endpoints/:nickname/stats", get(get_endpoint_stats))
+        .route("/api/usage", get(get_usage))
+        .route("/api/alerts", get(get_alerts))
+        .route("/api/alerts/open", get(get_open_alerts))
+        .route("/api/anomalies", get(get_anomalies))
+        .route("/api/percentiles", get(get_percentiles))
+        .route("/api/leaders", get(get_leaders))
+        .route("/api/cluster/events", get(get_cluster_events))
+        .route("/api/rounds/:id", get(get_round))
+        .route("/api/agent/samples", post(receive_agent_samples))
+        .route("/api/ingest", post(ingest_samples))
+        .route("/api/admin/errors", get(get_errors))
+        .route("/api/admin/incidents", get(get_incidents))
+        .route("/api/admin/backup", post(post_backup))
+        .route("/api/admin/endpoints/:nickname/pause", post(post_pause))
+        .route("/api/admin/endpoints/:nickname/resume", post(post_resume))
+        .route("/healthz", get(get_healthz))
+        .route("/readyz", get(get_readyz))
+        .layer(middleware::from_fn(timestamps::add_iso_timestamps))
+        .layer(middleware::from_fn_with_state(state.clone(), anonymize::anonymize_responses))
+        .with_state(state);
+
+    let app = match static_dir {
+        Some(dir) => app.nest_service("/static", get_service(ServeDir::new(dir))),
+        None => app.route("/static/*path", get(serve_asset)),
+    };
+    // gzip/brotli per Accept-Encoding; metrics and history responses run to several MB
+    let app = app.layer(CompressionLayer::new());
+
+    let server = axum::Server::bind(&addr);
+    log_info!("🚀 Server running on http://{}", addr);
+    systemd::notify("READY=1");
+    // Keeps serving (with /readyz failing) until the round in flight has been stored
+    server.serve(app.into_make_service()).with_graceful_shutdown(shutdown).await?;
 
     Ok(())
 }
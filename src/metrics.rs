@@ -1,12 +1,48 @@
 use axum::{
     extract::{Query, State},
-    Json,
+    http::{HeaderMap, StatusCode},
+    response::Response,
 };
-use rocksdb::DB;
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{BTreeMap, HashMap};
 
-use crate::models::{ConsensusStats, LeaderboardEntry, RPCResponse};
+use crate::access::{allowed, resolve_scope};
+use crate::etag::json_with_etag;
+use crate::models::{ConsensusStats, EndpointKind, HealthStatus, LeaderboardEntry, RPCResponse};
+use crate::pause;
+use crate::reference;
+use crate::retention::load_aggregates;
+use crate::state::AppState;
+use crate::status;
+use crate::tags::{tag_filter, tags_match};
+use crate::storage::is_sample_key;
+use crate::timestamps::parse_time;
+
+// Slot and block height come from separate calls, so allow some drift before
+// calling an endpoint's skipped-slot accounting out of line with the fleet
+const HEIGHT_DELTA_TOLERANCE: u64 = 4;
+
+// Median slot/block-height delta, plus the endpoints that stray too far from it
+fn block_height_outliers(responses: &[RPCResponse]) -> (Option<u64>, Vec<String>) {
+    let deltas: Vec<(String, u64)> = responses
+        .iter()
+        .filter(|r| r.slot > 0)
+        .filter_map(|r| r.block_height.map(|height| (r.label(), r.slot.saturating_sub(height))))
+        .collect();
+    let mut sorted: Vec<u64> = deltas.iter().map(|(_, delta)| *delta).collect();
+    sorted.sort_unstable();
+    let median = match sorted.get(sorted.len() / 2) {
+        Some(median) => *median,
+        None => return (None, Vec::new()),
+    };
+
+    let mut outliers: Vec<String> = deltas
+        .into_iter()
+        .filter(|(_, delta)| delta.abs_diff(median) > HEIGHT_DELTA_TOLERANCE)
+        .map(|(label, _)| label)
+        .collect();
+    outliers.sort();
+    (Some(median), outliers)
+}
 
 pub fn calculate_consensus(responses: &[RPCResponse]) -> ConsensusStats {
     if responses.is_empty() {
@@ -24,6 +60,14 @@ pub fn calculate_consensus(responses: &[RPCResponse]) -> ConsensusStats {
             slot_skew: String::from("No data"),
             latency_leaderboard: Vec::new(),
             slot_leaderboard: Vec::new(),
+            unhealthy_rpcs: Vec::new(),
+            median_height_delta: None,
+            block_height_outliers: Vec::new(),
+            throttled_pct: BTreeMap::new(),
+            endpoint_states: BTreeMap::new(),
+            protocol_delta_ms: BTreeMap::new(),
+            reference: None,
+            reference_lag: BTreeMap::new(),
         };
     }
 
@@ -71,10 +115,10 @@ pub fn calculate_consensus(responses: &[RPCResponse]) -> ConsensusStats {
     let mut latency_leaderboard: Vec<LeaderboardEntry> = responses
         .iter()
         .map(|r| LeaderboardEntry {
-            nickname: r.nickname.clone(),
+            nickname: r.label(),
             value: r.latency_ms as u64,
             latency_ms: r.latency_ms,
-            timestamp: r.timestamp,
+            timestamp: r.timestamp_secs(),
         })
         .collect();
     latency_leaderboard.sort_by_key(|entry| entry.value);
@@ -83,18 +127,27 @@ pub fn calculate_consensus(responses: &[RPCResponse]) -> ConsensusStats {
     let mut slot_leaderboard: Vec<LeaderboardEntry> = responses
         .iter()
         .map(|r| LeaderboardEntry {
-            nickname: r.nickname.clone(),
+            nickname: r.label(),
             value: r.slot,
             latency_ms: r.latency_ms,
-            timestamp: r.timestamp,
+            timestamp: r.timestamp_secs(),
         })
         .collect();
     slot_leaderboard.sort_by(|a, b| b.value.cmp(&a.value));
     slot_leaderboard.truncate(4);
 
+    let mut unhealthy_rpcs: Vec<String> = responses
+        .iter()
+        .filter(|r| matches!(r.health, Some(HealthStatus::Unhealthy { .. })))
+        .map(|r| r.label())
+        .collect();
+    unhealthy_rpcs.sort();
+
+    let (median_height_delta, block_height_outliers) = block_height_outliers(responses);
+
     ConsensusStats {
-        fastest_rpc: fastest.nickname.clone(),
-        slowest_rpc: slowest.nickname.clone(),
+        fastest_rpc: fastest.label(),
+        slowest_rpc: slowest.label(),
         fastest_latency: fastest.latency_ms,
         slowest_latency: slowest.latency_ms,
         consensus_blockhash: consensus_blockhash.0,
@@ -106,28 +159,88 @@ pub fn calculate_consensus(responses: &[RPCResponse]) -> ConsensusStats {
         slot_skew,
         latency_leaderboard,
         slot_leaderboard,
+        unhealthy_rpcs,
+        median_height_delta,
+        block_height_outliers,
+        throttled_pct: BTreeMap::new(),
+        endpoint_states: BTreeMap::new(),
+        protocol_delta_ms: BTreeMap::new(),
+        reference: None,
+        reference_lag: BTreeMap::new(),
+    }
+}
+
+// Share of samples per label that were rate limited; rollups weigh in with every sample
+// in their bucket rather than as a single entry
+fn throttled_pct(responses: &[RPCResponse]) -> BTreeMap<String, f64> {
+    let mut counts: BTreeMap<String, (u64, u64)> = BTreeMap::new();
+    for response in responses {
+        let (throttled, total) = counts.entry(response.label()).or_insert((0, 0));
+        match response.sample_count {
+            Some(count) => {
+                *total += count;
+                *throttled += response.throttled_count.unwrap_or(0);
+            }
+            None => {
+                *total += 1;
+                if response.throttled {
+                    *throttled += 1;
+                }
+            }
+        }
     }
+    counts
+        .into_iter()
+        .filter(|(_, (_, total))| *total > 0)
+        .map(|(label, (throttled, total))| (label, throttled as f64 / total as f64 * 100.0))
+        .collect()
+}
+
+// Average HTTP/1.1 minus HTTP/2 latency per label over samples timed both ways; positive
+// means HTTP/2 was faster
+fn protocol_delta_ms(responses: &[RPCResponse]) -> BTreeMap<String, f64> {
+    let mut sums: BTreeMap<String, (f64, u64)> = BTreeMap::new();
+    for response in responses {
+        if let (Some(http2), Some(http1)) = (response.http2_latency_ms, response.http1_latency_ms) {
+            let (sum, count) = sums.entry(response.label()).or_insert((0.0, 0));
+            *sum += http1 as f64 - http2 as f64;
+            *count += 1;
+        }
+    }
+    sums.into_iter()
+        .map(|(label, (sum, count))| (label, sum / count as f64))
+        .collect()
 }
 
 pub async fn get_metrics(
-    State(db): State<Arc<DB>>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
     Query(params): Query<HashMap<String, String>>,
-) -> Json<(Vec<RPCResponse>, ConsensusStats)> {
+) -> Result<Response, StatusCode> {
+    let scope = resolve_scope(&state, &headers)?;
+    let db = &state.db;
     let mut responses = Vec::new();
     let rpc_filter = params.get("rpc");
-    let from_ts = params.get("from").and_then(|ts| ts.parse::<i64>().ok());
-    let to_ts = params.get("to").and_then(|ts| ts.parse::<i64>().ok());
+    let tags = tag_filter(&params);
+    let from_ts = params.get("from").and_then(|ts| parse_time(ts));
+    let to_ts = params.get("to").and_then(|ts| parse_time(ts));
 
     let mut latest_by_rpc: HashMap<String, RPCResponse> = HashMap::new();
     let iter = db.iterator(rocksdb::IteratorMode::End);
 
     for item in iter {
         if let Ok((key, value)) = item {
+            if !is_sample_key(&key) {
+                continue;
+            }
             let key_str = String::from_utf8_lossy(&key);
             if let Ok(response) = serde_json::from_slice::<RPCResponse>(&value) {
-                if !latest_by_rpc.contains_key(&response.rpc_url) {
-                    latest_by_rpc.insert(response.rpc_url.clone(), response.clone());
+                if !tags_match(&response.tags, &tags) || !allowed(&scope, &response.nickname, &response.tags) {
+                    continue;
                 }
+                latest_by_rpc
+                    .entry(response.label())
+                    .or_insert_with(|| response.clone());
 
                 if let Some((url, _)) = key_str.split_once(':') {
                     let matches_rpc = rpc_filter
@@ -135,10 +248,10 @@ pub async fn get_metrics(
                         .map_or(true, |filter| url.contains(filter.as_str()));
                     let matches_time = match (from_ts, to_ts) {
                         (Some(from), Some(to)) => {
-                            response.timestamp >= from as f64 && response.timestamp <= to as f64
+                            response.timestamp_ms >= from * 1000 && response.timestamp_ms <= to * 1000
                         }
-                        (Some(from), None) => response.timestamp >= from as f64,
-                        (None, Some(to)) => response.timestamp <= to as f64,
+                        (Some(from), None) => response.timestamp_ms >= from * 1000,
+                        (None, Some(to)) => response.timestamp_ms <= to * 1000,
                         (None, None) => true,
                     };
 
@@ -150,13 +263,49 @@ pub async fn get_metrics(
         }
     }
 
-    responses.sort_by(|a, b| {
-        b.timestamp
-            .partial_cmp(&a.timestamp)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
+    // Older ranges are only available as rollups once the raw samples have aged out
+    if from_ts.is_some() || to_ts.is_some() {
+        responses.extend(load_aggregates(db, from_ts, to_ts).into_iter().filter(|r| {
+            rpc_filter.map_or(true, |filter| r.label().contains(filter.as_str()))
+                && tags_match(&r.tags, &tags)
+                && allowed(&scope, &r.nickname, &r.tags)
+        }));
+    }
+
+    responses.sort_by(|a, b| b.timestamp_ms.cmp(&a.timestamp_ms));
 
-    let consensus_stats = calculate_consensus(&latest_by_rpc.values().cloned().collect::<Vec<_>>());
+    // A paused endpoint's last sample goes stale, so it is left out of the consensus, as are
+    // Jito endpoints, which report no chain state
+    let current: Vec<RPCResponse> = latest_by_rpc
+        .values()
+        .filter(|r| !pause::is_paused(&r.nickname) && r.kind == EndpointKind::Rpc)
+        .cloned()
+        .collect();
+    let mut consensus_stats = calculate_consensus(&current);
+    consensus_stats.endpoint_states = status::snapshot();
+    if !tags.is_empty() || scope.is_some() {
+        consensus_stats
+            .endpoint_states
+            .retain(|label, _| latest_by_rpc.contains_key(label));
+    }
+    consensus_stats.throttled_pct = throttled_pct(&responses);
+    consensus_stats.protocol_delta_ms = protocol_delta_ms(&responses);
+
+    // Our own endpoints may share a lagging upstream, so a fresh stake-weighted view of the
+    // cluster takes precedence over their plurality
+    if let Some(view) = state
+        .config
+        .cluster_reference
+        .as_ref()
+        .and_then(|reference_config| reference::current(reference_config.max_age_secs()))
+    {
+        consensus_stats.consensus_slot = view.slot;
+        consensus_stats.reference_lag = current
+            .iter()
+            .map(|r| (r.label(), view.slot as i64 - r.slot as i64))
+            .collect();
+        consensus_stats.reference = Some(view);
+    }
 
     let public_responses: Vec<RPCResponse> = responses
         .into_iter()
@@ -166,5 +315,5 @@ pub async fn get_metrics(
         })
         .collect();
 
-    Json((public_responses, consensus_stats))
+    json_with_etag(&headers, &(public_responses, consensus_stats))
 }
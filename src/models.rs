@@ -1,19 +1,252 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+use crate::reference::ReferenceView;
+use crate::status::EndpointStatus;
+use crate::tags::Tags;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RPCResponse {
-    pub timestamp: f64,
+    /// Unix milliseconds; records from before the switch hold float seconds under "timestamp"
+    #[serde(alias = "timestamp", deserialize_with = "crate::timestamps::deserialize_millis")]
+    pub timestamp_ms: i64,
     pub slot: u64,
     pub blockhash: String,
     pub latency_ms: u128,
     pub rpc_url: String,
     pub nickname: String,
+    /// HTTP version that served the timing probe (e.g. "HTTP/2.0")
+    #[serde(default)]
+    pub protocol: Option<String>,
+    /// Remote IP address that served the timing probe
+    #[serde(default)]
+    pub remote_ip: Option<String>,
+    /// Probe region for samples shipped by a remote agent
+    #[serde(default)]
+    pub region: Option<String>,
+    /// Name of the external probe that pushed this sample via `/api/ingest`
+    #[serde(default)]
+    pub source: Option<String>,
+    /// Sanitized summary of why the sample is degraded (e.g. "timeout", "HTTP 429")
+    #[serde(default)]
+    pub error: Option<String>,
+    /// Set on downsampled history entries ("1m" or "1h"); raw samples leave it empty
+    #[serde(default)]
+    pub resolution: Option<String>,
+    /// The node's own `getHealth` verdict; empty if the call failed outright
+    #[serde(default)]
+    pub health: Option<HealthStatus>,
+    /// Finalized block height fetched alongside the slot, if the endpoint returned it
+    #[serde(default)]
+    pub block_height: Option<u64>,
+    /// Any call in this probe got an HTTP 429
+    #[serde(default)]
+    pub throttled: bool,
+    /// Retry-After the provider sent with the 429, if any
+    #[serde(default)]
+    pub retry_after_secs: Option<u64>,
+    /// The endpoint's tags at the time of the sample
+    #[serde(default)]
+    pub tags: Tags,
+    /// Side-by-side `getHealth` timings over each protocol, in protocol comparison mode
+    #[serde(default)]
+    pub http2_latency_ms: Option<u128>,
+    #[serde(default)]
+    pub http1_latency_ms: Option<u128>,
+    /// Kind of endpoint that produced the sample; only `rpc` samples carry chain state
+    #[serde(default)]
+    pub kind: EndpointKind,
+    /// Poll round that produced the sample; empty for agent, ingested and rollup entries
+    #[serde(default)]
+    pub round: Option<u64>,
+    /// Samples a downsampled entry covers, and how many of them were throttled; empty for raw samples
+    #[serde(default)]
+    pub sample_count: Option<u64>,
+    #[serde(default)]
+    pub throttled_count: Option<u64>,
+}
+
+/// Parsed `getHealth` result
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum HealthStatus {
+    Healthy,
+    Unhealthy {
+        num_slots_behind: Option<u64>,
+        message: String,
+    },
+}
+
+impl RPCResponse {
+    /// Sample time in unix seconds, for records that are still kept in seconds
+    pub fn timestamp_secs(&self) -> f64 {
+        self.timestamp_ms as f64 / 1000.0
+    }
+
+    /// Series label: the nickname, suffixed with "@region" for agent samples
+    pub fn label(&self) -> String {
+        match &self.region {
+            Some(region) => format!("{}@{}", self.nickname, region),
+            None => self.nickname.clone(),
+        }
+    }
+
+    /// Slots behind `highest_slot`; always 0 for endpoints that report no slot
+    pub fn slot_lag(&self, highest_slot: u64) -> u64 {
+        match self.kind {
+            EndpointKind::Rpc => highest_slot.saturating_sub(self.slot),
+            EndpointKind::Jito => 0,
+        }
+    }
 }
 
+/// Unredacted probe error, only served through the admin API
 #[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ErrorRecord {
+    /// Unix milliseconds; records from before the switch hold float seconds under "timestamp"
+    #[serde(alias = "timestamp", deserialize_with = "crate::timestamps::deserialize_millis")]
+    pub timestamp_ms: i64,
+    pub nickname: String,
+    pub rpc_url: String,
+    pub summary: String,
+    pub detail: String,
+}
+
+/// A round of samples shipped from an agent to the aggregator
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AgentBatch {
+    pub region: String,
+    pub samples: Vec<RPCResponse>,
+}
+
+/// What kind of service an endpoint is, which decides how it is probed
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum EndpointKind {
+    /// Solana JSON-RPC
+    #[default]
+    Rpc,
+    /// Jito block engine or relayer bundle API, timed with `getTipAccounts`
+    Jito,
+}
+
+/// Which HTTP protocol to use when probing an endpoint
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum HttpProtocol {
+    /// Negotiate HTTP/2 and fall back to HTTP/1.1 and the legacy client on failure
+    #[default]
+    Auto,
+    /// Always use HTTP/1.1
+    Http1,
+    /// Always use HTTP/2 (prior knowledge, no fallback)
+    Http2,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct RpcEndpoint {
     pub url: String,
     pub nickname: String,
+    /// WebSocket URL for subscription probes (defaults to `url` with ws:// or wss://)
+    #[serde(default)]
+    pub ws_url: Option<String>,
+    /// Overall request timeout in milliseconds (defaults to 30s)
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// TCP/TLS connect timeout in milliseconds (defaults to 10s)
+    #[serde(default)]
+    pub connect_timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub protocol: HttpProtocol,
+    /// `rpc` (default) or `jito` for a block engine / relayer URL ending in `/api/v1/bundles`
+    #[serde(default)]
+    pub kind: EndpointKind,
+    /// Also time every round over both HTTP/2 and HTTP/1.1; falls back to `rpc.compare_protocols`
+    #[serde(default)]
+    pub compare_protocols: Option<bool>,
+    /// Pin the endpoint's hostname to this IP instead of using DNS
+    #[serde(default)]
+    pub resolve_ip: Option<IpAddr>,
+    /// Outbound proxy (http://, https:// or socks5://); falls back to `rpc.proxy`
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// PEM bundle of additional root CAs to trust for this endpoint
+    #[serde(default)]
+    pub ca_cert: Option<String>,
+    /// PEM file with the client certificate for mutual TLS
+    #[serde(default)]
+    pub client_cert: Option<String>,
+    /// PEM file with the client private key (if not included in `client_cert`)
+    #[serde(default)]
+    pub client_key: Option<String>,
+    /// May be used to send the `[confirmation]` test transactions
+    #[serde(default)]
+    pub test_capable: bool,
+    /// Expected to keep full history; the archive depth probe flags it if its first block moves forward
+    #[serde(default)]
+    pub archival: bool,
+    /// TPU QUIC address ("host:port") checked by the TPU probe
+    #[serde(default)]
+    pub tpu: Option<String>,
+    /// Extra TCP ports (WebSocket, gRPC, ...) checked by the port probe
+    #[serde(default)]
+    pub ports: Vec<PortCheck>,
+    /// Requests per UTC day the monitor may send; probing slows down past 80% and stops at 100%
+    #[serde(default)]
+    pub daily_budget: Option<u64>,
+    /// Free-form labels such as provider, tier or region, used to filter the API
+    #[serde(default)]
+    pub tags: Tags,
+    /// Start with probing paused; resume through the admin API
+    #[serde(default)]
+    pub paused: bool,
+    /// Retries for individual calls; falls back to `rpc.retry` (default: no retries)
+    #[serde(default)]
+    pub retry: Option<RetryPolicy>,
+}
+
+/// Kinds of call failure that may be retried
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryClass {
+    /// Connect failures and connections reset mid-request
+    Connection,
+    Timeout,
+    /// HTTP 500-599 responses
+    Http5xx,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RetryPolicy {
+    /// Attempts per call including the first (default: 1, no retries)
+    pub max_attempts: Option<u32>,
+    /// Wait before the first retry, doubled for each further one (default: 100ms)
+    pub backoff_ms: Option<u64>,
+    /// Which failures are retried (default: connection)
+    #[serde(default = "default_retry_on")]
+    pub retry_on: Vec<RetryClass>,
+}
+
+fn default_retry_on() -> Vec<RetryClass> {
+    vec![RetryClass::Connection]
+}
+
+impl RetryPolicy {
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts.unwrap_or(1).max(1)
+    }
+
+    pub fn backoff_ms(&self) -> u64 {
+        self.backoff_ms.unwrap_or(100)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PortCheck {
+    /// Short label, e.g. "ws" or "grpc"
+    pub name: String,
+    pub port: u16,
 }
 
 #[derive(Debug, Serialize)]
@@ -39,4 +272,21 @@ pub struct ConsensusStats {
     pub slot_skew: String,
     pub latency_leaderboard: Vec<LeaderboardEntry>,
     pub slot_leaderboard: Vec<LeaderboardEntry>,
+    /// Labels of endpoints whose latest `getHealth` reported them unhealthy
+    pub unhealthy_rpcs: Vec<String>,
+    /// Fleet median of slot minus block height (the number of skipped slots)
+    pub median_height_delta: Option<u64>,
+    /// Labels of endpoints whose slot/block height delta strays from the fleet median
+    pub block_height_outliers: Vec<String>,
+    /// Share of returned samples per endpoint label that hit an HTTP 429
+    pub throttled_pct: BTreeMap<String, f64>,
+    /// Healthy/degraded/down state per endpoint label, as tracked by the live poller
+    pub endpoint_states: BTreeMap<String, EndpointStatus>,
+    /// Average HTTP/1.1 minus HTTP/2 latency per endpoint label, for samples timed over both
+    pub protocol_delta_ms: BTreeMap<String, f64>,
+    /// Stake-weighted slot of the `[cluster_reference]` nodes; while fresh, it is used as
+    /// `consensus_slot` instead of the plurality of our own endpoints
+    pub reference: Option<ReferenceView>,
+    /// Slots each endpoint's latest sample is behind the reference slot (negative if ahead)
+    pub reference_lag: BTreeMap<String, i64>,
 }
@@ -0,0 +1,84 @@
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+use tokio::net::{lookup_host, TcpStream};
+use tokio::process::Command;
+
+use crate::models::RpcEndpoint;
+
+// Host and port an endpoint's URL points at (scheme default if no explicit port)
+pub fn host_port(endpoint: &RpcEndpoint) -> Result<(String, u16), String> {
+    let url = reqwest::Url::parse(&endpoint.url).map_err(|e| format!("Invalid endpoint URL: {}", e))?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| format!("Endpoint URL has no host: {}", endpoint.url))?
+        .to_string();
+    let port = url
+        .port_or_known_default()
+        .ok_or_else(|| format!("Endpoint URL has no port: {}", endpoint.url))?;
+    Ok((host, port))
+}
+
+// Resolve the host, honouring the endpoint's `resolve_ip` pin like the HTTP client does
+async fn resolve(endpoint: &RpcEndpoint, host: &str, port: u16) -> Result<SocketAddr, String> {
+    if let Some(ip) = endpoint.resolve_ip {
+        return Ok(SocketAddr::new(ip, port));
+    }
+    lookup_host((host, port))
+        .await
+        .map_err(|e| format!("DNS lookup failed for {}: {}", host, e))?
+        .next()
+        .ok_or_else(|| format!("No addresses for {}", host))
+}
+
+/// Address the endpoint's URL resolves to (or its pinned `resolve_ip`)
+pub async fn resolve_endpoint(endpoint: &RpcEndpoint) -> Result<SocketAddr, String> {
+    let (host, port) = host_port(endpoint)?;
+    resolve(endpoint, &host, port).await
+}
+
+/// Time a bare TCP handshake (no TLS, no HTTP) to `port` on the endpoint's host
+pub async fn tcp_connect(endpoint: &RpcEndpoint, port: u16, timeout: Duration) -> Result<(SocketAddr, u128), String> {
+    let (host, _) = host_port(endpoint)?;
+    let address = resolve(endpoint, &host, port).await?;
+
+    let started = Instant::now();
+    match tokio::time::timeout(timeout, TcpStream::connect(address)).await {
+        Ok(Ok(_stream)) => Ok((address, started.elapsed().as_millis())),
+        Ok(Err(e)) => Err(format!("connect to {} failed: {}", address, e)),
+        Err(_) => Err(format!("connect to {} timed out", address)),
+    }
+}
+
+/// ICMP round trip via the system `ping` (raw sockets usually need privileges we don't have)
+pub async fn icmp_rtt(ip: IpAddr) -> Result<f64, String> {
+    let output = Command::new("ping")
+        .args(["-c", "1", "-W", "2", &ip.to_string()])
+        .output()
+        .await
+        .map_err(|e| format!("failed to run ping: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("no ICMP reply from {}", ip));
+    }
+
+    // e.g. "64 bytes from 1.2.3.4: icmp_seq=1 ttl=57 time=12.3 ms"
+    String::from_utf8_lossy(&output.stdout)
+        .split("time=")
+        .nth(1)
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|ms| ms.parse::<f64>().ok())
+        .ok_or_else(|| "unrecognised ping output".to_string())
+}
+
+/// Hop-by-hop path via the system `traceroute`, returned as its raw output
+pub async fn traceroute(ip: IpAddr, max_hops: u8) -> Result<String, String> {
+    let output = Command::new("traceroute")
+        .args(["-n", "-w", "2", "-q", "1", "-m", &max_hops.to_string(), &ip.to_string()])
+        .output()
+        .await
+        .map_err(|e| format!("failed to run traceroute: {}", e))?;
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    if stdout.trim().is_empty() {
+        return Err(format!("traceroute failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(stdout)
+}
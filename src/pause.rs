@@ -0,0 +1,89 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+use crate::admin::require_admin;
+use crate::models::RpcEndpoint;
+use crate::state::AppState;
+use crate::status;
+use crate::storage::{scan_prefix, Store, PAUSED_PREFIX};
+
+// Nicknames of endpoints whose probing is paused
+static PAUSED: Lazy<Mutex<BTreeSet<String>>> = Lazy::new(|| Mutex::new(BTreeSet::new()));
+
+pub fn is_paused(nickname: &str) -> bool {
+    PAUSED.lock().unwrap().contains(nickname)
+}
+
+// Pauses set through the API are keyed as "!paused:<nickname>" so they survive restarts
+fn paused_key(nickname: &str) -> String {
+    format!("{}{}", PAUSED_PREFIX, nickname)
+}
+
+/// Pause endpoints marked `paused` in the config or through the API before the restart
+pub fn load(db: &Store, endpoints: &[RpcEndpoint]) {
+    let stored = scan_prefix::<bool>(db, PAUSED_PREFIX)
+        .into_iter()
+        .map(|(key, _)| String::from_utf8_lossy(&key[PAUSED_PREFIX.len()..]).to_string());
+    let configured = endpoints.iter().filter(|e| e.paused).map(|e| e.nickname.clone());
+
+    let mut paused = PAUSED.lock().unwrap();
+    for nickname in configured.chain(stored) {
+        status::set_paused(&nickname, true);
+        paused.insert(nickname);
+    }
+}
+
+fn set(db: &Store, nickname: &str, pause: bool) -> Result<(), rocksdb::Error> {
+    if pause {
+        db.put(paused_key(nickname).as_bytes(), b"true")?;
+        PAUSED.lock().unwrap().insert(nickname.to_string());
+    } else {
+        db.delete(paused_key(nickname).as_bytes())?;
+        PAUSED.lock().unwrap().remove(nickname);
+    }
+    status::set_paused(nickname, pause);
+    log_info!("[{}] Probing {}", nickname, if pause { "paused" } else { "resumed" });
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+pub struct PauseState {
+    pub nickname: String,
+    pub paused: bool,
+}
+
+async fn update(state: AppState, headers: HeaderMap, nickname: String, pause: bool) -> Result<Json<PauseState>, StatusCode> {
+    require_admin(&state, &headers)?;
+    if !state.config.rpc.endpoints.iter().any(|e| e.nickname == nickname) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    set(&state.db, &nickname, pause).map_err(|e| {
+        log_error!("Error updating pause state for {}: {}", nickname, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(PauseState { nickname, paused: pause }))
+}
+
+// Stop probing an endpoint, e.g. for planned provider maintenance; its history stays visible
+pub async fn post_pause(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(nickname): Path<String>,
+) -> Result<Json<PauseState>, StatusCode> {
+    update(state, headers, nickname, true).await
+}
+
+pub async fn post_resume(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(nickname): Path<String>,
+) -> Result<Json<PauseState>, StatusCode> {
+    update(state, headers, nickname, false).await
+}
@@ -0,0 +1,730 @@
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::access::{allowed, resolve_scope, Scope};
+use crate::config::{
+    AccountConsistencyProbeConfig, ArchiveDepthProbeConfig, HeavyProbeConfig, PortsProbeConfig, ProbesConfig,
+    SignatureStatusProbeConfig, SimulateProbeConfig, VoteProbeConfig,
+};
+use crate::models::{EndpointKind, PortCheck, RpcEndpoint};
+use crate::netcheck::{host_port, icmp_rtt, tcp_connect};
+use crate::pause;
+use crate::quic::tpu_handshake;
+use crate::rpc::{call_method, CallError};
+use crate::state::AppState;
+use crate::storage::{scan_prefix, Store, HEAVY_PREFIX, PROBES_PREFIX};
+use crate::subscribe::spawn_account_subscribe;
+use crate::tags::{tag_filter, tags_match, Tags};
+use crate::usage;
+
+/// Outcome of one low-frequency probe (simulateTransaction etc.) against one endpoint
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProbeResult {
+    /// Unix milliseconds; records from before the switch hold float seconds under "timestamp"
+    #[serde(alias = "timestamp", deserialize_with = "crate::timestamps::deserialize_millis")]
+    pub timestamp_ms: i64,
+    pub kind: String,
+    pub nickname: String,
+    /// Round trip of the call; empty if it never got a response
+    pub latency_ms: Option<u128>,
+    pub success: bool,
+    /// Sanitized failure summary
+    pub error: Option<String>,
+    /// Probe-specific details (e.g. compute units consumed)
+    pub detail: Option<Value>,
+    #[serde(default)]
+    pub tags: Tags,
+}
+
+impl ProbeResult {
+    pub fn new(kind: &str, endpoint: &RpcEndpoint) -> Self {
+        ProbeResult {
+            timestamp_ms: chrono::Utc::now().timestamp_millis(),
+            kind: kind.to_string(),
+            nickname: endpoint.nickname.clone(),
+            latency_ms: None,
+            success: false,
+            error: None,
+            detail: None,
+            tags: endpoint.tags.clone(),
+        }
+    }
+
+    fn failed(mut self, error: CallError) -> Self {
+        self.error = Some(error.summary);
+        self
+    }
+}
+
+// Probe results are keyed as "<prefix><kind>:<nickname>:<ts_millis>"
+fn store_probe_result(db: &Store, prefix: &str, result: &ProbeResult) -> Result<(), Box<dyn std::error::Error>> {
+    let key = format!(
+        "{}{}:{}:{}",
+        prefix,
+        result.kind,
+        result.nickname,
+        result.timestamp_ms
+    );
+    db.put(key.as_bytes(), serde_json::to_vec(result)?)?;
+    Ok(())
+}
+
+// Endpoints that may be probed this round: not paused and within their request budget
+fn active(endpoints: &[RpcEndpoint]) -> Vec<RpcEndpoint> {
+    endpoints
+        .iter()
+        .filter(|e| !pause::is_paused(&e.nickname) && usage::allow_probe(e))
+        .cloned()
+        .collect()
+}
+
+// Run `probe` against every active endpoint concurrently and store each result
+async fn run_round<F, Fut>(endpoints: &[RpcEndpoint], db: &Store, probe: F)
+where
+    F: Fn(RpcEndpoint) -> Fut,
+    Fut: Future<Output = ProbeResult>,
+{
+    let results = join_all(active(endpoints).into_iter().map(probe)).await;
+    store_results(db, &results);
+}
+
+pub fn store_results(db: &Store, results: &[ProbeResult]) {
+    store_results_under(db, PROBES_PREFIX, results)
+}
+
+fn store_results_under(db: &Store, prefix: &str, results: &[ProbeResult]) {
+    for result in results {
+        if let Err(e) = store_probe_result(db, prefix, result) {
+            log_error!("Failed to store {} probe for {}: {}", result.kind, result.nickname, e);
+        }
+    }
+}
+
+// Run a round of `probe` once per `interval`
+fn spawn_every<F, Fut>(interval: Duration, endpoints: Vec<RpcEndpoint>, db: Arc<Store>, probe: F)
+where
+    F: Fn(RpcEndpoint) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ProbeResult> + Send + 'static,
+{
+    tokio::spawn(async move {
+        loop {
+            run_round(&endpoints, &db, &probe).await;
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+// simulateTransaction with a fixed transaction; the blockhash is replaced so it never expires
+async fn simulate(endpoint: RpcEndpoint, transaction: Arc<String>) -> ProbeResult {
+    let result = ProbeResult::new("simulate", &endpoint);
+    let params = Some(json!([
+        transaction.as_str(),
+        {"encoding": "base64", "sigVerify": false, "replaceRecentBlockhash": true}
+    ]));
+
+    match call_method::<Value>(&endpoint, "simulateTransaction", params).await {
+        Ok((response, latency_ms)) => {
+            let value = response.get("value").cloned().unwrap_or(Value::Null);
+            let err = value.get("err").cloned().unwrap_or(Value::Null);
+            ProbeResult {
+                latency_ms: Some(latency_ms),
+                success: err.is_null(),
+                error: (!err.is_null()).then(|| "simulation failed".to_string()),
+                detail: Some(json!({
+                    "err": err,
+                    "units_consumed": value.get("unitsConsumed"),
+                })),
+                ..result
+            }
+        }
+        Err(e) => result.failed(e),
+    }
+}
+
+fn spawn_simulate(config: &SimulateProbeConfig, endpoints: Vec<RpcEndpoint>, db: Arc<Store>) {
+    let transaction = Arc::new(config.transaction.clone());
+    spawn_every(Duration::from_secs(config.interval_secs()), endpoints, db, move |endpoint| {
+        simulate(endpoint, Arc::clone(&transaction))
+    });
+}
+
+// Pick a signature from the latest finalized block, trying each endpoint in turn
+async fn recent_signature(endpoints: &[RpcEndpoint]) -> Option<String> {
+    for endpoint in endpoints {
+        let slot = match call_method::<u64>(endpoint, "getSlot", Some(json!([{"commitment": "finalized"}]))).await {
+            Ok((slot, _)) => slot,
+            Err(_) => continue,
+        };
+        let params = Some(json!([slot, {
+            "commitment": "finalized",
+            "transactionDetails": "signatures",
+            "rewards": false,
+            "maxSupportedTransactionVersion": 0
+        }]));
+        if let Ok((block, _)) = call_method::<Value>(endpoint, "getBlock", params).await {
+            let signature = block
+                .get("signatures")
+                .and_then(|signatures| signatures.get(0))
+                .and_then(Value::as_str);
+            if let Some(signature) = signature {
+                return Some(signature.to_string());
+            }
+        }
+    }
+    None
+}
+
+// getSignatureStatuses for a single signature; a recent signature every node should know about
+async fn signature_status(endpoint: RpcEndpoint, signature: String, search_history: bool) -> ProbeResult {
+    let result = ProbeResult::new("signature_status", &endpoint);
+    let params = Some(json!([[signature.as_str()], {"searchTransactionHistory": search_history}]));
+
+    match call_method::<Value>(&endpoint, "getSignatureStatuses", params).await {
+        Ok((response, latency_ms)) => {
+            let status = response
+                .get("value")
+                .and_then(|value| value.get(0))
+                .cloned()
+                .unwrap_or(Value::Null);
+            let found = !status.is_null();
+            ProbeResult {
+                latency_ms: Some(latency_ms),
+                success: found,
+                error: (!found).then(|| "signature not found".to_string()),
+                detail: Some(json!({
+                    "signature": signature,
+                    "confirmation_status": status.get("confirmationStatus"),
+                })),
+                ..result
+            }
+        }
+        Err(e) => result.failed(e),
+    }
+}
+
+fn spawn_signature_status(config: &SignatureStatusProbeConfig, endpoints: Vec<RpcEndpoint>, db: Arc<Store>) {
+    let interval = Duration::from_secs(config.interval_secs());
+    let fixed_signature = config.signature.clone();
+    let search_history = config.search_history;
+
+    tokio::spawn(async move {
+        loop {
+            let signature = match &fixed_signature {
+                Some(signature) => Some(signature.clone()),
+                None => recent_signature(&endpoints).await,
+            };
+            match signature {
+                Some(signature) => {
+                    run_round(&endpoints, &db, |endpoint| {
+                        signature_status(endpoint, signature.clone(), search_history)
+                    })
+                    .await
+                }
+                None => log_error!("Signature status probe: no endpoint returned a recent block"),
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+// What one endpoint returned for the watched account
+struct AccountSnapshot {
+    context_slot: u64,
+    lamports: Option<u64>,
+    data_hash: Option<String>,
+}
+
+async fn fetch_account(endpoint: &RpcEndpoint, account: &str) -> Result<(AccountSnapshot, u128), CallError> {
+    let params = Some(json!([account, {"encoding": "base64", "commitment": "confirmed"}]));
+    let (response, latency_ms) = call_method::<Value>(endpoint, "getAccountInfo", params).await?;
+    let context_slot = response
+        .pointer("/context/slot")
+        .and_then(Value::as_u64)
+        .unwrap_or(0);
+    let value = response.get("value").filter(|value| !value.is_null());
+    let snapshot = AccountSnapshot {
+        context_slot,
+        lamports: value.and_then(|v| v.get("lamports")).and_then(Value::as_u64),
+        data_hash: value
+            .and_then(|v| v.pointer("/data/0"))
+            .and_then(Value::as_str)
+            .map(|data| solana_sdk::hash::hash(data.as_bytes()).to_string()),
+    };
+    Ok((snapshot, latency_ms))
+}
+
+// Fetch the account from every endpoint in the same round and compare each against the
+// majority (lamports, data) pair. Endpoints answering from an older slot are expected to
+// differ now and then; consistent divergence points at stale data.
+async fn account_consistency_round(endpoints: &[RpcEndpoint], account: &str) -> Vec<ProbeResult> {
+    let snapshots = join_all(endpoints.iter().map(|endpoint| fetch_account(endpoint, account))).await;
+
+    let mut state_counts: HashMap<(Option<u64>, Option<String>), usize> = HashMap::new();
+    for (snapshot, _) in snapshots.iter().flatten() {
+        *state_counts
+            .entry((snapshot.lamports, snapshot.data_hash.clone()))
+            .or_insert(0) += 1;
+    }
+    let majority = state_counts
+        .into_iter()
+        .max_by_key(|(_, count)| *count)
+        .map(|(state, _)| state);
+
+    let slots: Vec<u64> = snapshots.iter().flatten().map(|(s, _)| s.context_slot).collect();
+    let highest_slot = slots.iter().copied().max().unwrap_or(0);
+    let slot_spread = highest_slot - slots.iter().copied().min().unwrap_or(0);
+
+    endpoints
+        .iter()
+        .zip(snapshots)
+        .map(|(endpoint, snapshot)| {
+            let result = ProbeResult::new("account_consistency", endpoint);
+            match snapshot {
+                Ok((snapshot, latency_ms)) => {
+                    let matches = majority.as_ref()
+                        == Some(&(snapshot.lamports, snapshot.data_hash.clone()));
+                    ProbeResult {
+                        latency_ms: Some(latency_ms),
+                        success: matches,
+                        error: (!matches).then(|| "account state diverges".to_string()),
+                        detail: Some(json!({
+                            "context_slot": snapshot.context_slot,
+                            "slots_behind": highest_slot - snapshot.context_slot,
+                            "context_slot_spread": slot_spread,
+                            "lamports": snapshot.lamports,
+                            "data_hash": snapshot.data_hash,
+                        })),
+                        ..result
+                    }
+                }
+                Err(e) => result.failed(e),
+            }
+        })
+        .collect()
+}
+
+fn spawn_account_consistency(config: &AccountConsistencyProbeConfig, endpoints: Vec<RpcEndpoint>, db: Arc<Store>) {
+    let interval = Duration::from_secs(config.interval_secs());
+    let account = config.account.clone();
+
+    tokio::spawn(async move {
+        loop {
+            let results = account_consistency_round(&active(&endpoints), &account).await;
+            store_results(&db, &results);
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+// How much history the endpoint retains: first available block, optionally the lowest
+// ledger slot, and the distance from the current slot
+async fn archive_depth(endpoint: &RpcEndpoint, include_ledger_slot: bool) -> Result<(u64, Value, u128), CallError> {
+    let (first_block, latency_ms) = call_method::<u64>(endpoint, "getFirstAvailableBlock", None).await?;
+    let (slot, _) = call_method::<u64>(endpoint, "getSlot", None).await?;
+    let minimum_ledger_slot = if include_ledger_slot {
+        Some(call_method::<u64>(endpoint, "minimumLedgerSlot", None).await?.0)
+    } else {
+        None
+    };
+
+    let detail = json!({
+        "first_available_block": first_block,
+        "minimum_ledger_slot": minimum_ledger_slot,
+        "depth_slots": slot.saturating_sub(first_block),
+    });
+    Ok((first_block, detail, latency_ms))
+}
+
+fn spawn_archive_depth(config: &ArchiveDepthProbeConfig, endpoints: Vec<RpcEndpoint>, db: Arc<Store>) {
+    let interval = Duration::from_secs(config.interval_secs());
+    let include_ledger_slot = config.minimum_ledger_slot;
+
+    tokio::spawn(async move {
+        // Last first-available block seen per archival endpoint; it should never move forward
+        let mut baselines: HashMap<String, u64> = HashMap::new();
+        loop {
+            let active = active(&endpoints);
+            let depths = join_all(active.iter().map(|endpoint| archive_depth(endpoint, include_ledger_slot))).await;
+            let results: Vec<ProbeResult> = active
+                .iter()
+                .zip(depths)
+                .map(|(endpoint, depth)| {
+                    let result = ProbeResult::new("archive_depth", endpoint);
+                    let (first_block, detail, latency_ms) = match depth {
+                        Ok(depth) => depth,
+                        Err(e) => return result.failed(e),
+                    };
+                    let shrank = endpoint.archival
+                        && baselines
+                            .get(&endpoint.nickname)
+                            .map_or(false, |baseline| first_block > *baseline);
+                    if shrank {
+                        log_error!(
+                            "[{}] Archive depth shrank: first available block {} -> {}",
+                            endpoint.nickname, baselines[&endpoint.nickname], first_block
+                        );
+                    }
+                    if endpoint.archival {
+                        baselines.insert(endpoint.nickname.clone(), first_block);
+                    }
+                    ProbeResult {
+                        latency_ms: Some(latency_ms),
+                        success: !shrank,
+                        error: shrank.then(|| "archive depth shrank".to_string()),
+                        detail: Some(detail),
+                        ..result
+                    }
+                })
+                .collect();
+            store_results(&db, &results);
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+// One configured heavy call; `kind` is the probe's name so each one is stored and charted separately
+async fn heavy_call(endpoint: RpcEndpoint, probe: Arc<HeavyProbeConfig>) -> ProbeResult {
+    let result = ProbeResult::new(&probe.name, &endpoint);
+
+    match call_method::<Value>(&endpoint, &probe.method, probe.params.clone()).await {
+        Ok((response, latency_ms)) => ProbeResult {
+            latency_ms: Some(latency_ms),
+            success: true,
+            detail: Some(json!({
+                "method": probe.method,
+                "response_bytes": serde_json::to_string(&response).map(|body| body.len()).unwrap_or(0),
+            })),
+            ..result
+        },
+        Err(e) => result.failed(e),
+    }
+}
+
+fn spawn_heavy(probe: &HeavyProbeConfig, endpoints: Vec<RpcEndpoint>, db: Arc<Store>) {
+    if probe.name.is_empty() || probe.name.contains(':') {
+        log_error!("Skipping heavy probe {:?}: names must be non-empty and must not contain ':'", probe.name);
+        return;
+    }
+    let interval = Duration::from_secs(probe.interval_secs());
+    let probe = Arc::new(probe.clone());
+
+    tokio::spawn(async move {
+        loop {
+            let results = join_all(
+                active(&endpoints)
+                    .into_iter()
+                    .map(|endpoint| heavy_call(endpoint, Arc::clone(&probe))),
+            )
+            .await;
+            store_results_under(&db, HEAVY_PREFIX, &results);
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+// TCP handshake time to the endpoint's HTTP(S) port, plus optional ICMP RTT, to separate
+// a slow network path from a slow RPC node
+async fn network(endpoint: RpcEndpoint, icmp: bool) -> ProbeResult {
+    let result = ProbeResult::new("network", &endpoint);
+    let port = match host_port(&endpoint) {
+        Ok((_, port)) => port,
+        Err(e) => return result.failed(CallError::from(e)),
+    };
+    let timeout = Duration::from_millis(endpoint.connect_timeout_ms.unwrap_or(10_000));
+
+    match tcp_connect(&endpoint, port, timeout).await {
+        Ok((address, connect_ms)) => {
+            let icmp_rtt_ms = if icmp { icmp_rtt(address.ip()).await.ok() } else { None };
+            ProbeResult {
+                latency_ms: Some(connect_ms),
+                success: true,
+                detail: Some(json!({
+                    "address": address.to_string(),
+                    "tcp_connect_ms": connect_ms,
+                    "icmp_rtt_ms": icmp_rtt_ms,
+                })),
+                ..result
+            }
+        }
+        Err(e) => result.failed(CallError::new("connection failed", e)),
+    }
+}
+
+// QUIC handshake with the provider's TPU address
+async fn tpu(endpoint: RpcEndpoint) -> ProbeResult {
+    let result = ProbeResult::new("tpu", &endpoint);
+    let address = match endpoint.tpu.as_deref().map(tokio::net::lookup_host) {
+        Some(lookup) => match lookup.await.ok().and_then(|mut addresses| addresses.next()) {
+            Some(address) => address,
+            None => return result.failed(CallError::new("connection failed", "TPU address did not resolve")),
+        },
+        None => return result.failed(CallError::new("client error", "no tpu address configured")),
+    };
+    let timeout = Duration::from_millis(endpoint.connect_timeout_ms.unwrap_or(10_000));
+
+    match tpu_handshake(address, timeout).await {
+        Ok(handshake_ms) => ProbeResult {
+            latency_ms: Some(handshake_ms),
+            success: true,
+            detail: Some(json!({ "address": address.to_string() })),
+            ..result
+        },
+        Err(e) => ProbeResult {
+            detail: Some(json!({ "address": address.to_string() })),
+            ..result.failed(CallError::new("QUIC handshake failed", e))
+        },
+    }
+}
+
+// Reachability and connect time of one of the endpoint's extra ports; stored as kind "port_<name>"
+async fn port_check(endpoint: RpcEndpoint, check: PortCheck) -> ProbeResult {
+    let result = ProbeResult::new(&format!("port_{}", check.name), &endpoint);
+    let timeout = Duration::from_millis(endpoint.connect_timeout_ms.unwrap_or(10_000));
+    let detail = json!({ "name": check.name, "port": check.port });
+
+    match tcp_connect(&endpoint, check.port, timeout).await {
+        Ok((_, connect_ms)) => ProbeResult {
+            latency_ms: Some(connect_ms),
+            success: true,
+            detail: Some(detail),
+            ..result
+        },
+        Err(e) => ProbeResult {
+            detail: Some(detail),
+            ..result.failed(CallError::new("connection failed", e))
+        },
+    }
+}
+
+fn spawn_port_checks(config: &PortsProbeConfig, endpoints: Vec<RpcEndpoint>, db: Arc<Store>) {
+    let interval = Duration::from_secs(config.interval_secs());
+    let checks: Vec<(RpcEndpoint, PortCheck)> = endpoints
+        .iter()
+        .flat_map(|endpoint| endpoint.ports.iter().map(move |check| (endpoint.clone(), check.clone())))
+        .filter(|(endpoint, check)| {
+            let valid = !check.name.is_empty() && !check.name.contains(':');
+            if !valid {
+                log_error!("[{}] Skipping port check {:?}: names must be non-empty and must not contain ':'", endpoint.nickname, check.name);
+            }
+            valid
+        })
+        .collect();
+
+    tokio::spawn(async move {
+        loop {
+            let active: HashSet<String> = active(&endpoints).into_iter().map(|e| e.nickname).collect();
+            let results = join_all(
+                checks
+                    .iter()
+                    .filter(|(endpoint, _)| active.contains(&endpoint.nickname))
+                    .cloned()
+                    .map(|(endpoint, check)| port_check(endpoint, check)),
+            )
+            .await;
+            store_results(&db, &results);
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+// The watched account's latest vote as one endpoint sees it
+struct VoteSnapshot {
+    last_vote: u64,
+    root_slot: Option<u64>,
+    delinquent: bool,
+}
+
+async fn fetch_vote(endpoint: &RpcEndpoint, vote_account: &str) -> Result<(Option<VoteSnapshot>, u128), CallError> {
+    let params = Some(json!([{"votePubkey": vote_account, "commitment": "processed"}]));
+    let (response, latency_ms) = call_method::<Value>(endpoint, "getVoteAccounts", params).await?;
+    let find = |list: &str| {
+        response
+            .get(list)
+            .and_then(Value::as_array)
+            .and_then(|accounts| accounts.first())
+            .cloned()
+    };
+    let account = match (find("current"), find("delinquent")) {
+        (Some(account), _) => Some((account, false)),
+        (None, Some(account)) => Some((account, true)),
+        (None, None) => None,
+    };
+    let snapshot = account.and_then(|(account, delinquent)| {
+        Some(VoteSnapshot {
+            last_vote: account.get("lastVote")?.as_u64()?,
+            root_slot: account.get("rootSlot").and_then(Value::as_u64),
+            delinquent,
+        })
+    });
+    Ok((snapshot, latency_ms))
+}
+
+// Poll every endpoint for the account's last vote. `votes_behind_slots` is the gap to the
+// freshest endpoint this round; `visibility_delay_ms` is how long a newer vote has already been
+// visible on some endpoint (0 for endpoints showing the newest one), at round resolution.
+fn spawn_vote(config: &VoteProbeConfig, endpoints: Vec<RpcEndpoint>, db: Arc<Store>) {
+    let interval = Duration::from_secs(config.interval_secs());
+    let vote_account = config.vote_account.clone();
+
+    tokio::spawn(async move {
+        // When each vote slot was first seen on any endpoint
+        let mut first_seen: BTreeMap<u64, f64> = BTreeMap::new();
+        loop {
+            let active = active(&endpoints);
+            let snapshots = join_all(active.iter().map(|endpoint| fetch_vote(endpoint, &vote_account))).await;
+            let now = chrono::Utc::now().timestamp_millis() as f64 / 1000.0;
+
+            for (snapshot, _) in snapshots.iter().flatten() {
+                if let Some(snapshot) = snapshot {
+                    first_seen.entry(snapshot.last_vote).or_insert(now);
+                }
+            }
+            // Votes older than an hour no longer help measure anything
+            first_seen.retain(|_, seen| now - *seen < 3600.0);
+            let newest = first_seen.keys().next_back().copied().unwrap_or(0);
+
+            let results: Vec<ProbeResult> = active
+                .iter()
+                .zip(snapshots)
+                .map(|(endpoint, snapshot)| {
+                    let result = ProbeResult::new("vote", endpoint);
+                    match snapshot {
+                        Ok((Some(snapshot), latency_ms)) => {
+                            let newer_since = first_seen
+                                .range(snapshot.last_vote + 1..)
+                                .map(|(_, seen)| *seen)
+                                .fold(now, f64::min);
+                            ProbeResult {
+                                latency_ms: Some(latency_ms),
+                                success: true,
+                                detail: Some(json!({
+                                    "vote_account": vote_account,
+                                    "last_vote": snapshot.last_vote,
+                                    "root_slot": snapshot.root_slot,
+                                    "delinquent": snapshot.delinquent,
+                                    "votes_behind_slots": newest.saturating_sub(snapshot.last_vote),
+                                    "visibility_delay_ms": ((now - newer_since) * 1000.0) as u64,
+                                })),
+                                ..result
+                            }
+                        }
+                        Ok((None, latency_ms)) => ProbeResult {
+                            latency_ms: Some(latency_ms),
+                            error: Some("vote account not found".to_string()),
+                            ..result
+                        },
+                        Err(e) => result.failed(e),
+                    }
+                })
+                .collect();
+            store_results(&db, &results);
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+/// Most recent stored result of `kind` for one endpoint
+pub fn latest_result(db: &Store, kind: &str, nickname: &str) -> Option<ProbeResult> {
+    scan_prefix::<ProbeResult>(db, &format!("{}{}:{}:", PROBES_PREFIX, kind, nickname))
+        .pop()
+        .map(|(_, result)| result)
+}
+
+// Start every probe type configured under `[probes]`
+pub fn spawn_probes(config: &ProbesConfig, endpoints: Vec<RpcEndpoint>, db: Arc<Store>) {
+    // Jito endpoints only speak the bundle API, so they just get the transport-level probes
+    let rpc_endpoints: Vec<RpcEndpoint> = endpoints.iter().filter(|e| e.kind == EndpointKind::Rpc).cloned().collect();
+    if let Some(simulate) = &config.simulate {
+        spawn_simulate(simulate, rpc_endpoints.clone(), Arc::clone(&db));
+    }
+    if let Some(signature_status) = &config.signature_status {
+        spawn_signature_status(signature_status, rpc_endpoints.clone(), Arc::clone(&db));
+    }
+    if let Some(account_consistency) = &config.account_consistency {
+        spawn_account_consistency(account_consistency, rpc_endpoints.clone(), Arc::clone(&db));
+    }
+    if let Some(archive_depth) = &config.archive_depth {
+        spawn_archive_depth(archive_depth, rpc_endpoints.clone(), Arc::clone(&db));
+    }
+    for heavy in &config.heavy {
+        spawn_heavy(heavy, rpc_endpoints.clone(), Arc::clone(&db));
+    }
+    if let Some(account_subscribe) = &config.account_subscribe {
+        spawn_account_subscribe(account_subscribe, rpc_endpoints.clone(), Arc::clone(&db));
+    }
+    if let Some(network_config) = &config.network {
+        let icmp = network_config.icmp;
+        spawn_every(Duration::from_secs(network_config.interval_secs()), endpoints.clone(), Arc::clone(&db), move |endpoint| {
+            network(endpoint, icmp)
+        });
+    }
+    if let Some(tpu_config) = &config.tpu {
+        let with_tpu: Vec<RpcEndpoint> = endpoints.iter().filter(|e| e.tpu.is_some()).cloned().collect();
+        spawn_every(Duration::from_secs(tpu_config.interval_secs()), with_tpu, Arc::clone(&db), tpu);
+    }
+    if let Some(vote_config) = &config.vote {
+        spawn_vote(vote_config, rpc_endpoints.clone(), Arc::clone(&db));
+    }
+    if let Some(ports_config) = &config.ports {
+        spawn_port_checks(ports_config, endpoints, db);
+    }
+}
+
+// Results under `prefix`, newest first, narrowed by the optional `kind`/`rpc`/`tags`/`limit` params
+fn load_results(
+    db: &Store,
+    prefix: &str,
+    kind: Option<&String>,
+    params: &HashMap<String, String>,
+    scope: &Option<Scope>,
+) -> Vec<ProbeResult> {
+    let prefix = match (kind, params.get("rpc")) {
+        (Some(kind), Some(nickname)) => format!("{}{}:{}:", prefix, kind, nickname),
+        (Some(kind), None) => format!("{}{}:", prefix, kind),
+        (None, _) => prefix.to_string(),
+    };
+    let limit = params
+        .get("limit")
+        .and_then(|l| l.parse::<usize>().ok())
+        .unwrap_or(100);
+
+    let tags = tag_filter(params);
+    let mut results: Vec<ProbeResult> = scan_prefix::<ProbeResult>(db, &prefix)
+        .into_iter()
+        .map(|(_, result)| result)
+        .filter(|result| tags_match(&result.tags, &tags) && allowed(scope, &result.nickname, &result.tags))
+        .collect();
+    results.sort_by(|a, b| b.timestamp_ms.cmp(&a.timestamp_ms));
+    results.truncate(limit);
+    results
+}
+
+// Recent probe results, newest first. Optional `kind`, `rpc` (nickname; requires `kind`) and `limit` params.
+pub async fn get_probes(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<ProbeResult>>, StatusCode> {
+    let scope = resolve_scope(&state, &headers)?;
+    Ok(Json(load_results(&state.db, PROBES_PREFIX, params.get("kind"), &params, &scope)))
+}
+
+// Recent heavy-call results, newest first. Optional `name`, `rpc` (nickname; requires `name`) and `limit` params.
+pub async fn get_heavy(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<ProbeResult>>, StatusCode> {
+    let scope = resolve_scope(&state, &headers)?;
+    Ok(Json(load_results(&state.db, HEAVY_PREFIX, params.get("name"), &params, &scope)))
+}
@@ -0,0 +1,105 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::storage::{scan_prefix, Store, PROTOCOL_STATS_PREFIX};
+
+/// How the data calls of one probe were served
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// HTTP/2, or whatever `auto` negotiated on the first try
+    Http2,
+    /// Pinned to HTTP/1.1
+    Http1,
+    /// `auto` fell back to HTTP/1.1 after the first try failed
+    Http1Fallback,
+    /// Both HTTP attempts failed and the legacy RpcClient got an answer
+    LegacyFallback,
+    /// Nothing got an answer
+    Failed,
+}
+
+/// Lifetime probe counts for one endpoint by how they were served
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ProtocolStats {
+    pub http2: u64,
+    pub http1: u64,
+    pub http1_fallback: u64,
+    pub legacy_fallback: u64,
+    pub failed: u64,
+    /// Individual calls retried under the endpoint's retry policy (not probes)
+    #[serde(default)]
+    pub retries: u64,
+}
+
+impl ProtocolStats {
+    pub fn total(&self) -> u64 {
+        self.http2 + self.http1 + self.http1_fallback + self.legacy_fallback + self.failed
+    }
+
+    /// Share of probes that needed a fallback, in percent
+    pub fn fallback_pct(&self) -> f64 {
+        match self.total() {
+            0 => 0.0,
+            total => (self.http1_fallback + self.legacy_fallback) as f64 / total as f64 * 100.0,
+        }
+    }
+}
+
+static STATS: Lazy<Mutex<HashMap<String, ProtocolStats>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Count one probe of an endpoint
+pub fn record(nickname: &str, outcome: Outcome) {
+    let mut stats = STATS.lock().unwrap();
+    let entry = stats.entry(nickname.to_string()).or_default();
+    match outcome {
+        Outcome::Http2 => entry.http2 += 1,
+        Outcome::Http1 => entry.http1 += 1,
+        Outcome::Http1Fallback => entry.http1_fallback += 1,
+        Outcome::LegacyFallback => entry.legacy_fallback += 1,
+        Outcome::Failed => entry.failed += 1,
+    }
+}
+
+/// Count one retried call
+pub fn record_retry(nickname: &str) {
+    STATS.lock().unwrap().entry(nickname.to_string()).or_default().retries += 1;
+}
+
+pub fn get(nickname: &str) -> ProtocolStats {
+    STATS.lock().unwrap().get(nickname).cloned().unwrap_or_default()
+}
+
+// Stats are keyed as "!pstats:<nickname>"
+fn stats_key(nickname: &str) -> String {
+    format!("{}{}", PROTOCOL_STATS_PREFIX, nickname)
+}
+
+/// Restore persisted counters at startup
+pub fn load(db: &Store) {
+    let mut stats = STATS.lock().unwrap();
+    for (key, record) in scan_prefix::<ProtocolStats>(db, PROTOCOL_STATS_PREFIX) {
+        let key = String::from_utf8_lossy(&key);
+        stats.insert(key[PROTOCOL_STATS_PREFIX.len()..].to_string(), record);
+    }
+}
+
+// Persist the counters every minute
+pub fn spawn_flush(db: Arc<Store>) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            let stats = STATS.lock().unwrap().clone();
+            for (nickname, record) in stats {
+                let result = serde_json::to_vec(&record)
+                    .map_err(|e| e.to_string())
+                    .and_then(|body| db.put(stats_key(&nickname).as_bytes(), body).map_err(|e| e.to_string()));
+                if let Err(e) = result {
+                    log_error!("Failed to store protocol stats for {}: {}", nickname, e);
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,88 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{ring, CryptoProvider};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, SignatureScheme};
+
+// ALPN protocol validators expect on their TPU QUIC port
+const TPU_ALPN: &[u8] = b"solana-tpu";
+
+// Validators present self-signed certificates, so there is nothing to verify against;
+// the probe only cares whether the handshake completes and how long it takes
+#[derive(Debug)]
+struct SkipServerVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for SkipServerVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+fn client_config() -> Result<quinn::ClientConfig, Box<dyn std::error::Error + Send + Sync>> {
+    let provider = Arc::new(ring::default_provider());
+    let mut crypto = rustls::ClientConfig::builder_with_provider(Arc::clone(&provider))
+        .with_protocol_versions(&[&rustls::version::TLS13])?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(SkipServerVerification(provider)))
+        .with_no_client_auth();
+    crypto.alpn_protocols = vec![TPU_ALPN.to_vec()];
+
+    let quic_crypto = quinn::crypto::rustls::QuicClientConfig::try_from(crypto)?;
+    Ok(quinn::ClientConfig::new(Arc::new(quic_crypto)))
+}
+
+/// Time a QUIC handshake with a TPU address; the connection is closed straight away
+pub async fn tpu_handshake(address: SocketAddr, timeout: Duration) -> Result<u128, String> {
+    let bind: SocketAddr = if address.is_ipv6() { "[::]:0" } else { "0.0.0.0:0" }
+        .parse()
+        .map_err(|e| format!("{}", e))?;
+    let mut endpoint = quinn::Endpoint::client(bind).map_err(|e| format!("failed to open UDP socket: {}", e))?;
+    endpoint.set_default_client_config(client_config().map_err(|e| format!("invalid QUIC config: {}", e))?);
+
+    let started = Instant::now();
+    let connecting = endpoint
+        .connect(address, "solana-tpu")
+        .map_err(|e| format!("connect to {} failed: {}", address, e))?;
+    let connection = match tokio::time::timeout(timeout, connecting).await {
+        Ok(Ok(connection)) => connection,
+        Ok(Err(e)) => return Err(format!("handshake with {} failed: {}", address, e)),
+        Err(_) => return Err(format!("handshake with {} timed out", address)),
+    };
+    let handshake_ms = started.elapsed().as_millis();
+
+    connection.close(0u32.into(), b"probe");
+    endpoint.wait_idle().await;
+    Ok(handshake_ms)
+}
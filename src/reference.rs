@@ -0,0 +1,144 @@
+use futures::future::join_all;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::ClusterReferenceConfig;
+use crate::models::RpcEndpoint;
+use crate::rpc::call_method;
+
+// Stake is re-read this often; it only changes at epoch boundaries
+const STAKE_REFRESH: Duration = Duration::from_secs(600);
+
+/// Where the cluster is according to the reference nodes, weighted by their stake
+#[derive(Debug, Serialize, Clone)]
+pub struct ReferenceView {
+    pub timestamp: f64,
+    /// Highest finalized slot that nodes holding at least half the answering stake have reached
+    pub slot: u64,
+    /// Reference nodes that answered with a slot and hold stake
+    pub nodes: usize,
+    /// Share of the reference nodes' total stake that answered
+    pub stake_pct: f64,
+}
+
+static VIEW: Lazy<Mutex<Option<ReferenceView>>> = Lazy::new(|| Mutex::new(None));
+
+/// The latest view, unless it is older than `max_age_secs`
+pub fn current(max_age_secs: u64) -> Option<ReferenceView> {
+    let now = chrono::Utc::now().timestamp_millis() as f64 / 1000.0;
+    VIEW.lock()
+        .unwrap()
+        .clone()
+        .filter(|view| now - view.timestamp <= max_age_secs as f64)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VoteAccount {
+    node_pubkey: String,
+    activated_stake: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct VoteAccounts {
+    current: Vec<VoteAccount>,
+    delinquent: Vec<VoteAccount>,
+}
+
+// Activated stake per validator identity, from the first reference node that answers
+async fn fetch_stakes(nodes: &[RpcEndpoint]) -> Option<HashMap<String, u64>> {
+    for node in nodes {
+        match call_method::<VoteAccounts>(node, "getVoteAccounts", None).await {
+            Ok((accounts, _)) => {
+                let mut stakes = HashMap::new();
+                for account in accounts.current.into_iter().chain(accounts.delinquent) {
+                    *stakes.entry(account.node_pubkey).or_insert(0) += account.activated_stake;
+                }
+                return Some(stakes);
+            }
+            Err(e) => log_error!("[{}] Reference stake lookup failed: {}", node.nickname, e),
+        }
+    }
+    None
+}
+
+// The highest slot that at least half of the stake has reached: walk the answers from the
+// highest slot down until the stake seen so far makes a majority
+fn stake_weighted_slot(mut answers: Vec<(u64, u64)>) -> Option<u64> {
+    let total: u64 = answers.iter().map(|(_, stake)| stake).sum();
+    answers.sort_unstable_by(|a, b| b.0.cmp(&a.0));
+    let mut seen = 0;
+    for (slot, stake) in answers {
+        seen += stake;
+        if seen * 2 >= total {
+            return Some(slot);
+        }
+    }
+    None
+}
+
+/// Poll the reference nodes' finalized slot every `interval_secs`
+pub fn spawn_reference(config: ClusterReferenceConfig) {
+    let nodes: Vec<(RpcEndpoint, String)> = config
+        .nodes
+        .iter()
+        .map(|node| {
+            let endpoint = RpcEndpoint {
+                url: node.url.clone(),
+                nickname: format!("reference-{}", &node.identity[..node.identity.len().min(8)]),
+                timeout_ms: Some(config.timeout_ms()),
+                ..Default::default()
+            };
+            (endpoint, node.identity.clone())
+        })
+        .collect();
+    let endpoints: Vec<RpcEndpoint> = nodes.iter().map(|(endpoint, _)| endpoint.clone()).collect();
+    let interval = Duration::from_secs(config.interval_secs());
+
+    tokio::spawn(async move {
+        let mut stakes: HashMap<String, u64> = HashMap::new();
+        let mut stakes_loaded: Option<Instant> = None;
+        loop {
+            if stakes_loaded.map_or(true, |loaded| loaded.elapsed() >= STAKE_REFRESH) {
+                if let Some(fresh) = fetch_stakes(&endpoints).await {
+                    for (_, identity) in nodes.iter().filter(|(_, identity)| !fresh.contains_key(identity)) {
+                        log_error!("Reference node {} holds no stake and is ignored", identity);
+                    }
+                    stakes = fresh;
+                    stakes_loaded = Some(Instant::now());
+                }
+            }
+
+            let slots = join_all(
+                endpoints
+                    .iter()
+                    .map(|endpoint| call_method::<u64>(endpoint, "getSlot", Some(json!([{"commitment": "finalized"}])))),
+            )
+            .await;
+            let node_stake = |identity: &str| stakes.get(identity).copied().unwrap_or(0);
+            let total_stake: u64 = nodes.iter().map(|(_, identity)| node_stake(identity)).sum();
+            let answers: Vec<(u64, u64)> = nodes
+                .iter()
+                .zip(slots)
+                .filter_map(|((_, identity), slot)| Some((slot.ok()?.0, node_stake(identity))))
+                .filter(|(_, stake)| *stake > 0)
+                .collect();
+            let answered_stake: u64 = answers.iter().map(|(_, stake)| stake).sum();
+            let answered = answers.len();
+
+            if let Some(slot) = stake_weighted_slot(answers) {
+                *VIEW.lock().unwrap() = Some(ReferenceView {
+                    timestamp: chrono::Utc::now().timestamp_millis() as f64 / 1000.0,
+                    slot,
+                    nodes: answered,
+                    stake_pct: answered_stake as f64 / total_stake.max(1) as f64 * 100.0,
+                });
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
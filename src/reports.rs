@@ -0,0 +1,186 @@
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::check::sample_errors;
+use crate::models::RPCResponse;
+use crate::stats::percentile;
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportSchedule {
+    #[default]
+    Daily,
+    Weekly,
+}
+
+#[derive(Default)]
+struct EndpointTally {
+    samples: u64,
+    ok: u64,
+    latencies: Vec<u32>,
+    worst_slot_lag: u64,
+    incidents: u64,
+    failing: bool,
+}
+
+struct Accumulator {
+    schedule: ReportSchedule,
+    period_start: DateTime<Utc>,
+    // When tallying actually began: `period_start`, or later if the monitor started mid-period
+    covered_from: DateTime<Utc>,
+    endpoints: BTreeMap<String, EndpointTally>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EndpointSummary {
+    pub nickname: String,
+    pub samples: u64,
+    pub uptime_pct: f64,
+    pub p95_latency_ms: u32,
+    pub worst_slot_lag: u64,
+    pub incidents: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SlaReport {
+    pub schedule: String,
+    pub period_start: String,
+    pub period_end: String,
+    /// Start of the data the report is based on; later than `period_start` when the monitor
+    /// was (re)started during the period, since tallies are kept in memory
+    pub covered_from: String,
+    /// Whether `covered_from` is later than `period_start`
+    pub partial: bool,
+    pub endpoints: Vec<EndpointSummary>,
+}
+
+// None while reporting is disabled, so nothing accumulates unbounded
+static ACCUMULATOR: Lazy<Mutex<Option<Accumulator>>> = Lazy::new(|| Mutex::new(None));
+
+// Daily periods start at 00:00 UTC, weekly ones on Monday 00:00 UTC
+fn period_start(schedule: ReportSchedule, now: DateTime<Utc>) -> DateTime<Utc> {
+    let mut date = now.date_naive();
+    if schedule == ReportSchedule::Weekly {
+        date -= chrono::Duration::days(date.weekday().num_days_from_monday() as i64);
+    }
+    Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+}
+
+// Fold one poll round into the running tallies for the current period
+pub fn record_round(samples: &[RPCResponse]) {
+    let mut guard = ACCUMULATOR.lock().unwrap();
+    let accumulator = match guard.as_mut() {
+        Some(accumulator) => accumulator,
+        None => return,
+    };
+
+    let healthy: Vec<&RPCResponse> = samples.iter().filter(|s| sample_errors(s).is_empty()).collect();
+    let highest_slot = healthy.iter().map(|s| s.slot).max().unwrap_or(0);
+
+    for sample in samples {
+        let tally = accumulator.endpoints.entry(sample.label()).or_default();
+        let ok = sample_errors(sample).is_empty();
+
+        tally.samples += 1;
+        if ok {
+            tally.ok += 1;
+            tally.latencies.push(sample.latency_ms.min(u32::MAX as u128) as u32);
+            tally.worst_slot_lag = tally.worst_slot_lag.max(sample.slot_lag(highest_slot));
+        } else if !tally.failing {
+            tally.incidents += 1;
+        }
+        tally.failing = !ok;
+    }
+}
+
+fn summarize(accumulator: Accumulator, period_end: DateTime<Utc>) -> SlaReport {
+    let endpoints = accumulator
+        .endpoints
+        .into_iter()
+        .map(|(nickname, mut tally)| {
+            tally.latencies.sort_unstable();
+            EndpointSummary {
+                nickname,
+                samples: tally.samples,
+                uptime_pct: tally.ok as f64 * 100.0 / tally.samples.max(1) as f64,
+                p95_latency_ms: percentile(&tally.latencies, 95.0),
+                worst_slot_lag: tally.worst_slot_lag,
+                incidents: tally.incidents,
+            }
+        })
+        .collect();
+
+    SlaReport {
+        schedule: format!("{:?}", accumulator.schedule).to_lowercase(),
+        period_start: accumulator.period_start.to_rfc3339(),
+        period_end: period_end.to_rfc3339(),
+        covered_from: accumulator.covered_from.to_rfc3339(),
+        partial: accumulator.covered_from > accumulator.period_start,
+        endpoints,
+    }
+}
+
+async fn deliver(report: &SlaReport, output_dir: Option<&PathBuf>, webhook_url: Option<&str>) {
+    if let Some(dir) = output_dir {
+        let name = format!("sla-{}-{}.json", report.schedule, &report.period_start[..10]);
+        let result = std::fs::create_dir_all(dir)
+            .map_err(|e| e.to_string())
+            .and_then(|_| serde_json::to_vec_pretty(report).map_err(|e| e.to_string()))
+            .and_then(|body| std::fs::write(dir.join(&name), body).map_err(|e| e.to_string()));
+        match result {
+            Ok(()) => log_info!("📄 Wrote SLA report {}", dir.join(&name).display()),
+            Err(e) => log_error!("Error writing SLA report {}: {}", name, e),
+        }
+    }
+
+    if let Some(url) = webhook_url {
+        match reqwest::Client::new().post(url).json(report).send().await {
+            Ok(response) if response.status().is_success() => {}
+            Ok(response) => log_error!("SLA report webhook returned HTTP {}", response.status()),
+            Err(e) => log_error!("Error posting SLA report: {}", e),
+        }
+    }
+}
+
+// Start accumulating and emit a report whenever a daily/weekly period closes
+pub fn spawn_reports(schedule: ReportSchedule, output_dir: Option<PathBuf>, webhook_url: Option<String>) {
+    let now = Utc::now();
+    *ACCUMULATOR.lock().unwrap() = Some(Accumulator {
+        schedule,
+        period_start: period_start(schedule, now),
+        covered_from: now,
+        endpoints: BTreeMap::new(),
+    });
+
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
+
+            let start = period_start(schedule, Utc::now());
+            let finished = {
+                let mut guard = ACCUMULATOR.lock().unwrap();
+                match guard.as_mut() {
+                    Some(current) if current.period_start != start => Some(std::mem::replace(
+                        current,
+                        Accumulator {
+                            schedule,
+                            period_start: start,
+                            covered_from: start,
+                            endpoints: BTreeMap::new(),
+                        },
+                    )),
+                    _ => None,
+                }
+            };
+
+            if let Some(accumulator) = finished {
+                let report = summarize(accumulator, start);
+                deliver(&report, output_dir.as_ref(), webhook_url.as_deref()).await;
+            }
+        }
+    });
+}
@@ -0,0 +1,389 @@
+use chrono::Utc;
+use rocksdb::WriteBatch;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::alerts::AlertEvent;
+use crate::anomaly::AnomalyEvent;
+use crate::check::sample_errors;
+use crate::cluster::ClusterEvent;
+use crate::config::RetentionConfig;
+use crate::confirmation::ConfirmationRecord;
+use crate::incidents::IncidentRecord;
+use crate::leaders::LeaderRound;
+use crate::models::{EndpointKind, ErrorRecord, RPCResponse};
+use crate::probes::ProbeResult;
+use crate::rounds::RoundRecord;
+use crate::storage::{is_sample_key, scan_prefix, scan_prefix_raw, Store, AGG_1H_PREFIX, AGG_1M_PREFIX, ALERTS_PREFIX, ANOMALIES_PREFIX, CLUSTER_EVENTS_PREFIX, CONFIRMATIONS_PREFIX, ERRORS_PREFIX, HEAVY_PREFIX, HISTOGRAMS_PREFIX, INCIDENTS_PREFIX, LEADER_ROUNDS_PREFIX, PROBES_PREFIX, ROUNDS_PREFIX, USAGE_PREFIX};
+use crate::tags::Tags;
+
+/// Downsampled samples for one series over one time bucket
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AggregateRecord {
+    pub bucket_start: i64,
+    pub nickname: String,
+    #[serde(default)]
+    pub region: Option<String>,
+    #[serde(default)]
+    pub tags: Tags,
+    /// Every sample in the bucket, healthy or not
+    pub count: u64,
+    pub error_count: u64,
+    #[serde(default)]
+    pub throttled_count: u64,
+    pub latency_avg_ms: f64,
+    pub latency_min_ms: u128,
+    pub latency_max_ms: u128,
+    pub slot_max: u64,
+    pub blockhash_last: String,
+    #[serde(default)]
+    pub kind: EndpointKind,
+}
+
+impl AggregateRecord {
+    fn empty(bucket_start: i64, nickname: String, region: Option<String>, tags: Tags) -> Self {
+        AggregateRecord {
+            bucket_start,
+            nickname,
+            region,
+            tags,
+            count: 0,
+            error_count: 0,
+            throttled_count: 0,
+            latency_avg_ms: 0.0,
+            latency_min_ms: u128::MAX,
+            latency_max_ms: 0,
+            slot_max: 0,
+            blockhash_last: String::new(),
+            kind: EndpointKind::default(),
+        }
+    }
+
+    // Samples that went into the latency and slot fields
+    fn healthy_count(&self) -> u64 {
+        self.count.saturating_sub(self.error_count)
+    }
+
+    fn add_sample(&mut self, sample: &RPCResponse) {
+        self.kind = sample.kind;
+        let healthy = self.healthy_count();
+        self.count += 1;
+        if sample.throttled {
+            self.throttled_count += 1;
+        }
+        if !sample_errors(sample).is_empty() {
+            self.error_count += 1;
+            return;
+        }
+        self.latency_avg_ms = (self.latency_avg_ms * healthy as f64 + sample.latency_ms as f64) / (healthy + 1) as f64;
+        self.latency_min_ms = self.latency_min_ms.min(sample.latency_ms);
+        self.latency_max_ms = self.latency_max_ms.max(sample.latency_ms);
+        if sample.slot >= self.slot_max {
+            self.slot_max = sample.slot;
+            self.blockhash_last = sample.blockhash.clone();
+        }
+    }
+
+    fn merge(&mut self, other: &AggregateRecord) {
+        let healthy = self.healthy_count();
+        let other_healthy = other.healthy_count();
+        if healthy + other_healthy > 0 {
+            self.latency_avg_ms = (self.latency_avg_ms * healthy as f64
+                + other.latency_avg_ms * other_healthy as f64)
+                / (healthy + other_healthy) as f64;
+        }
+        self.latency_min_ms = self.latency_min_ms.min(other.latency_min_ms);
+        self.latency_max_ms = self.latency_max_ms.max(other.latency_max_ms);
+        if other.slot_max >= self.slot_max {
+            self.slot_max = other.slot_max;
+            self.blockhash_last = other.blockhash_last.clone();
+        }
+        self.count += other.count;
+        self.error_count += other.error_count;
+        self.throttled_count += other.throttled_count;
+    }
+
+    fn label(&self) -> String {
+        match &self.region {
+            Some(region) => format!("{}@{}", self.nickname, region),
+            None => self.nickname.clone(),
+        }
+    }
+
+    /// Present the bucket in the same shape as a raw sample for the history API. Failed samples
+    /// are summarized in `error`; when none succeeded, the latency is 0 and only `error` counts.
+    pub fn to_response(&self, resolution: &str) -> RPCResponse {
+        RPCResponse {
+            timestamp_ms: self.bucket_start * 1000,
+            slot: self.slot_max,
+            blockhash: self.blockhash_last.clone(),
+            latency_ms: self.latency_avg_ms.round() as u128,
+            rpc_url: String::new(),
+            nickname: self.nickname.clone(),
+            protocol: None,
+            remote_ip: None,
+            region: self.region.clone(),
+            source: None,
+            error: (self.error_count > 0).then(|| format!("{}/{} samples failed", self.error_count, self.count)),
+            resolution: Some(resolution.to_string()),
+            health: None,
+            block_height: None,
+            throttled: self.throttled_count > 0,
+            retry_after_secs: None,
+            tags: self.tags.clone(),
+            http2_latency_ms: None,
+            http1_latency_ms: None,
+            kind: self.kind,
+            round: None,
+            sample_count: Some(self.count),
+            throttled_count: Some(self.throttled_count),
+        }
+    }
+}
+
+fn bucket_key(prefix: &str, label: &str, bucket_start: i64) -> String {
+    format!("{}{}:{}", prefix, label, bucket_start)
+}
+
+// Merge freshly rolled-up buckets into whatever is already stored under the same keys
+fn write_buckets(db: &Store, batch: &mut WriteBatch, buckets: HashMap<String, AggregateRecord>) -> Result<(), Box<dyn std::error::Error>> {
+    for (key, mut record) in buckets {
+        if let Some(existing) = db.get(key.as_bytes())? {
+            if let Ok(existing) = serde_json::from_slice::<AggregateRecord>(&existing) {
+                record.merge(&existing);
+            }
+        }
+        batch.put(key.as_bytes(), serde_json::to_vec(&record)?);
+    }
+    Ok(())
+}
+
+// Raw samples -> 1-minute buckets -> 1-hour buckets -> deleted, each at its own cutoff
+pub async fn run_retention(db: Arc<Store>, config: &RetentionConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let now = Utc::now().timestamp();
+    let raw_cutoff = now - config.raw_secs();
+    let minute_cutoff = now - config.minute_secs();
+    let hour_cutoff = now - config.hour_secs();
+
+    let mut batch = WriteBatch::default();
+    let mut minute_buckets: HashMap<String, AggregateRecord> = HashMap::new();
+    let mut hour_buckets: HashMap<String, AggregateRecord> = HashMap::new();
+
+    for item in db.iterator(rocksdb::IteratorMode::Start) {
+        if let Ok((key, value)) = item {
+            if key.starts_with(ERRORS_PREFIX.as_bytes()) {
+                if let Ok(record) = serde_json::from_slice::<ErrorRecord>(&value) {
+                    if record.timestamp_ms < raw_cutoff * 1000 {
+                        batch.delete(key);
+                    }
+                }
+            } else if is_sample_key(&key) {
+                if let Ok(response) = serde_json::from_slice::<RPCResponse>(&value) {
+                    if response.timestamp_ms < raw_cutoff * 1000 {
+                        let bucket_start = response.timestamp_ms.div_euclid(60_000) * 60;
+                        minute_buckets
+                            .entry(bucket_key(AGG_1M_PREFIX, &response.label(), bucket_start))
+                            .or_insert_with(|| AggregateRecord::empty(bucket_start, response.nickname.clone(), response.region.clone(), response.tags.clone()))
+                            .add_sample(&response);
+                        batch.delete(key);
+                    }
+                }
+            }
+        }
+    }
+
+    for (_, record) in scan_prefix::<AggregateRecord>(&db, AGG_1M_PREFIX) {
+        if record.bucket_start < minute_cutoff {
+            let bucket_start = record.bucket_start.div_euclid(3600) * 3600;
+            let label = record.label();
+            hour_buckets
+                .entry(bucket_key(AGG_1H_PREFIX, &label, bucket_start))
+                .or_insert_with(|| AggregateRecord::empty(bucket_start, record.nickname.clone(), record.region.clone(), record.tags.clone()))
+                .merge(&record);
+            batch.delete(bucket_key(AGG_1M_PREFIX, &label, record.bucket_start).as_bytes());
+        }
+    }
+
+    for (key, record) in scan_prefix::<AggregateRecord>(&db, AGG_1H_PREFIX) {
+        if record.bucket_start < hour_cutoff {
+            batch.delete(key);
+        }
+    }
+
+    // A round's index is only useful while its raw samples exist
+    for (key, record) in scan_prefix::<RoundRecord>(&db, ROUNDS_PREFIX) {
+        if record.timestamp_ms < raw_cutoff * 1000 {
+            batch.delete(key);
+        }
+    }
+
+    // Incidents, alerts and cluster events are rare and useful for escalations, so they live as long as the 1-hour rollups
+    for (key, record) in scan_prefix::<IncidentRecord>(&db, INCIDENTS_PREFIX) {
+        if record.timestamp_ms < hour_cutoff * 1000 {
+            batch.delete(key);
+        }
+    }
+
+    for (key, event) in scan_prefix::<AlertEvent>(&db, ALERTS_PREFIX) {
+        if event.timestamp_ms < hour_cutoff * 1000 {
+            batch.delete(key);
+        }
+    }
+    for (key, event) in scan_prefix::<ClusterEvent>(&db, CLUSTER_EVENTS_PREFIX) {
+        if event.started_at_ms < hour_cutoff * 1000 {
+            batch.delete(key);
+        }
+    }
+
+    // Confirmations, anomalies, leader rounds and low-frequency probes are kept as long as the 1-minute rollups
+    for (key, record) in scan_prefix::<ConfirmationRecord>(&db, CONFIRMATIONS_PREFIX) {
+        if record.timestamp_ms < minute_cutoff * 1000 {
+            batch.delete(key);
+        }
+    }
+    for (key, event) in scan_prefix::<AnomalyEvent>(&db, ANOMALIES_PREFIX) {
+        if event.timestamp_ms < minute_cutoff * 1000 {
+            batch.delete(key);
+        }
+    }
+    for (key, round) in scan_prefix::<LeaderRound>(&db, LEADER_ROUNDS_PREFIX) {
+        if round.timestamp < minute_cutoff as f64 {
+            batch.delete(key);
+        }
+    }
+    for prefix in [PROBES_PREFIX, HEAVY_PREFIX] {
+        for (key, record) in scan_prefix::<ProbeResult>(&db, prefix) {
+            if record.timestamp_ms < minute_cutoff * 1000 {
+                batch.delete(key);
+            }
+        }
+    }
+
+    // Latency histograms are keyed by minute start and kept as long as the 1-hour rollups
+    for (key, _) in scan_prefix_raw(&db, HISTOGRAMS_PREFIX) {
+        let bucket = String::from_utf8_lossy(&key)
+            .rsplit(':')
+            .next()
+            .and_then(|bucket| bucket.parse::<i64>().ok());
+        if bucket.map_or(false, |bucket| bucket < hour_cutoff) {
+            batch.delete(key);
+        }
+    }
+
+    // Usage counters are keyed by hour start and kept as long as the 1-hour rollups
+    for (key, _) in scan_prefix::<u64>(&db, USAGE_PREFIX) {
+        let hour = String::from_utf8_lossy(&key)
+            .rsplit(':')
+            .next()
+            .and_then(|hour| hour.parse::<i64>().ok());
+        if hour.map_or(false, |hour| hour < hour_cutoff) {
+            batch.delete(key);
+        }
+    }
+
+    write_buckets(&db, &mut batch, minute_buckets)?;
+    write_buckets(&db, &mut batch, hour_buckets)?;
+
+    db.write(batch)?;
+    Ok(())
+}
+
+/// Downsampled history within [from, to], shaped like raw samples
+pub fn load_aggregates(db: &Store, from: Option<i64>, to: Option<i64>) -> Vec<RPCResponse> {
+    [(AGG_1M_PREFIX, "1m"), (AGG_1H_PREFIX, "1h")]
+        .iter()
+        .flat_map(|(prefix, resolution)| {
+            scan_prefix::<AggregateRecord>(db, prefix)
+                .into_iter()
+                .filter(|(_, r)| from.map_or(true, |from| r.bucket_start >= from))
+                .filter(|(_, r)| to.map_or(true, |to| r.bucket_start <= to))
+                .map(move |(_, r)| r.to_response(resolution))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(latency_ms: u128, healthy: bool) -> RPCResponse {
+        RPCResponse {
+            timestamp_ms: 1_700_000_000_000,
+            slot: 100,
+            blockhash: "hash".to_string(),
+            latency_ms,
+            rpc_url: "https://rpc.example.com".to_string(),
+            nickname: "a".to_string(),
+            protocol: Some("HTTP/1.1".to_string()),
+            remote_ip: None,
+            region: None,
+            source: None,
+            error: (!healthy).then(|| "timeout".to_string()),
+            resolution: None,
+            health: None,
+            block_height: None,
+            throttled: false,
+            retry_after_secs: None,
+            tags: Tags::default(),
+            http2_latency_ms: None,
+            http1_latency_ms: None,
+            kind: EndpointKind::Rpc,
+            round: None,
+            sample_count: None,
+            throttled_count: None,
+        }
+    }
+
+    #[test]
+    fn bucket_with_more_errors_than_healthy_samples() {
+        let mut record = AggregateRecord::empty(0, "a".to_string(), None, Tags::default());
+        for _ in 0..3 {
+            record.add_sample(&sample(0, false));
+        }
+        record.add_sample(&sample(100, true));
+        record.add_sample(&sample(200, true));
+
+        assert_eq!(record.count, 5);
+        assert_eq!(record.error_count, 3);
+        assert_eq!(record.latency_avg_ms, 150.0);
+
+        let mut other = AggregateRecord::empty(0, "a".to_string(), None, Tags::default());
+        other.add_sample(&sample(0, false));
+        other.add_sample(&sample(300, true));
+        record.merge(&other);
+
+        assert_eq!(record.count, 7);
+        assert_eq!(record.error_count, 4);
+        assert_eq!(record.latency_avg_ms, 200.0);
+        assert_eq!(record.latency_max_ms, 300);
+    }
+
+    #[test]
+    fn rollup_reports_its_failures() {
+        let mut healthy = AggregateRecord::empty(0, "a".to_string(), None, Tags::default());
+        healthy.add_sample(&sample(100, true));
+        let response = healthy.to_response("1m");
+        assert_eq!(response.error, None);
+        assert!(sample_errors(&response).is_empty());
+
+        let mut failed = AggregateRecord::empty(0, "a".to_string(), None, Tags::default());
+        for _ in 0..3 {
+            failed.add_sample(&sample(0, false));
+        }
+        let response = failed.to_response("1m");
+        assert_eq!(response.error.as_deref(), Some("3/3 samples failed"));
+        assert_eq!(sample_errors(&response), vec!["3/3 samples failed".to_string()]);
+    }
+
+    #[test]
+    fn rollup_carries_its_throttle_counts() {
+        let mut record = AggregateRecord::empty(0, "a".to_string(), None, Tags::default());
+        for throttled in [true, false, false, false] {
+            record.add_sample(&RPCResponse { throttled, ..sample(100, true) });
+        }
+        let response = record.to_response("1m");
+        assert_eq!(response.sample_count, Some(4));
+        assert_eq!(response.throttled_count, Some(1));
+    }
+}
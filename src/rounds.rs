@@ -0,0 +1,182 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use rocksdb::{Direction, IteratorMode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::access::{allowed, resolve_scope};
+use crate::models::RPCResponse;
+use crate::rpc::sample_key;
+use crate::state::AppState;
+use crate::storage::{first_in_prefix, last_in_prefix, Store, ROUNDS_PREFIX, ROUND_COUNTER_KEY};
+use crate::tags::{tag_filter, tags_match};
+
+// Next round to hand out; persisted so IDs keep increasing across restarts
+static NEXT_ROUND: AtomicU64 = AtomicU64::new(1);
+
+/// The samples one poll round stored
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RoundRecord {
+    pub id: u64,
+    /// Unix milliseconds; records from before the switch hold float seconds under "timestamp"
+    #[serde(alias = "timestamp", deserialize_with = "crate::timestamps::deserialize_millis")]
+    pub timestamp_ms: i64,
+    /// Keys of the round's samples
+    pub keys: Vec<String>,
+}
+
+/// Continue numbering after the last round handed out before the restart
+pub fn load(db: &Store) {
+    let next = db
+        .get(ROUND_COUNTER_KEY.as_bytes())
+        .ok()
+        .flatten()
+        .and_then(|value| String::from_utf8(value).ok())
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(1);
+    NEXT_ROUND.store(next, Ordering::SeqCst);
+}
+
+/// Hand out the ID for a new poll round
+pub fn next(db: &Store) -> u64 {
+    let round = NEXT_ROUND.fetch_add(1, Ordering::SeqCst);
+    if let Err(e) = db.put(ROUND_COUNTER_KEY.as_bytes(), (round + 1).to_string().as_bytes()) {
+        log_error!("Error storing the round counter: {}", e);
+    }
+    round
+}
+
+// Rounds are keyed as "!round:<id>", zero-padded so they sort numerically
+fn round_key(id: u64) -> String {
+    format!("{}{:020}", ROUNDS_PREFIX, id)
+}
+
+/// Index the samples stored for a round so they can be fetched together
+pub fn record(db: &Store, id: u64, samples: &[RPCResponse]) {
+    let record = RoundRecord {
+        id,
+        timestamp_ms: samples.iter().map(|s| s.timestamp_ms).min().unwrap_or_default(),
+        keys: samples.iter().map(sample_key).collect(),
+    };
+    if record.keys.is_empty() {
+        return;
+    }
+    let result = serde_json::to_vec(&record)
+        .map_err(|e| e.to_string())
+        .and_then(|body| db.put(round_key(id).as_bytes(), body).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        log_error!("Error storing round {}: {}", id, e);
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Round {
+    pub id: u64,
+    pub timestamp_ms: i64,
+    pub samples: Vec<RPCResponse>,
+}
+
+// Every sample of one poll round, while its raw samples are retained
+pub async fn get_round(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<u64>,
+) -> Result<Json<Round>, StatusCode> {
+    let scope = resolve_scope(&state, &headers)?;
+
+    let record = state
+        .db
+        .get(round_key(id).as_bytes())
+        .map_err(|e| {
+            log_error!("Error reading round {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .and_then(|value| serde_json::from_slice::<RoundRecord>(&value).ok())
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let samples = record
+        .keys
+        .iter()
+        .filter_map(|key| state.db.get(key.as_bytes()).ok().flatten())
+        .filter_map(|value| serde_json::from_slice::<RPCResponse>(&value).ok())
+        .filter(|sample| sample.round == Some(id) && allowed(&scope, &sample.nickname, &sample.tags))
+        .map(|mut sample| {
+            sample.rpc_url = String::new();
+            sample
+        })
+        .collect();
+
+    Ok(Json(Round {
+        id,
+        timestamp_ms: record.timestamp_ms,
+        samples,
+    }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct Delta {
+    /// Pass back as `since` to fetch only what was stored after this response
+    pub cursor: u64,
+    /// The requested cursor is no longer retained (or is newer than anything stored, e.g. after
+    /// the database was wiped), so the client should reload the full window from `/api/metrics`
+    pub reset: bool,
+    pub samples: Vec<RPCResponse>,
+}
+
+// Samples of the poll rounds after `since` (a round ID; 0 or absent for everything retained),
+// oldest first, plus the cursor for the next call. Optional `rpc`, `tags` and `limit`
+// (rounds, default 100) params. Agent and ingested samples belong to no round and are not included.
+pub async fn get_delta(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Delta>, StatusCode> {
+    let scope = resolve_scope(&state, &headers)?;
+    let since = params
+        .get("since")
+        .and_then(|since| since.parse::<u64>().ok())
+        .unwrap_or(0);
+    let limit = params
+        .get("limit")
+        .and_then(|l| l.parse::<usize>().ok())
+        .unwrap_or(100);
+    let tags = tag_filter(&params);
+
+    let start = round_key(since + 1);
+    let records: Vec<RoundRecord> = state
+        .db
+        .iterator(IteratorMode::From(start.as_bytes(), Direction::Forward))
+        .filter_map(|item| item.ok())
+        .take_while(|(key, _)| key.starts_with(ROUNDS_PREFIX.as_bytes()))
+        .filter_map(|(_, value)| serde_json::from_slice::<RoundRecord>(&value).ok())
+        .take(limit)
+        .collect();
+
+    // A cursor always names a stored round; gaps between IDs are rounds that stored no samples
+    let oldest = first_in_prefix::<RoundRecord>(&state.db, ROUNDS_PREFIX).map_or(0, |(_, record)| record.id);
+    let newest = last_in_prefix::<RoundRecord>(&state.db, ROUNDS_PREFIX).map_or(0, |(_, record)| record.id);
+    let reset = since > 0 && (since < oldest || since > newest);
+    let cursor = records.last().map_or(since.min(newest), |record| record.id);
+    let samples = records
+        .iter()
+        .flat_map(|record| record.keys.iter().map(move |key| (record.id, key)))
+        .filter_map(|(id, key)| {
+            let value = state.db.get(key.as_bytes()).ok().flatten()?;
+            serde_json::from_slice::<RPCResponse>(&value)
+                .ok()
+                .filter(|sample| sample.round == Some(id))
+        })
+        .filter(|sample| params.get("rpc").map_or(true, |rpc| sample.label().contains(rpc.as_str())))
+        .filter(|sample| tags_match(&sample.tags, &tags) && allowed(&scope, &sample.nickname, &sample.tags))
+        .map(|mut sample| {
+            sample.rpc_url = String::new();
+            sample
+        })
+        .collect();
+
+    Ok(Json(Delta { cursor, reset, samples }))
+}
@@ -1,49 +1,193 @@
-use crate::models::{RPCResponse, RpcEndpoint};
-use chrono::Utc;
-use rocksdb::DB;
-use solana_client::rpc_client::RpcClient;
-use std::sync::Arc;
+use crate::anonymize;
+use crate::models::{EndpointKind, ErrorRecord, HealthStatus, HttpProtocol, RPCResponse, RetryClass, RpcEndpoint};
+use crate::protocol_stats::{self, Outcome};
+use crate::rounds::RoundRecord;
+use crate::storage::{is_sample_key, scan_prefix, Store, ERRORS_PREFIX, ROUNDS_PREFIX, SAMPLE_KEYS_MS_KEY};
+use crate::usage;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use once_cell::sync::Lazy;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use uuid::Uuid;
-use std::sync::atomic::{AtomicU64, Ordering};
-
-// Connection statistics
-static HTTP2_REQUESTS: AtomicU64 = AtomicU64::new(0);
-static FALLBACK_REQUESTS: AtomicU64 = AtomicU64::new(0);
-
-// Global HTTP/2 client with connection pooling
-static HTTP_CLIENT: Lazy<Client> = Lazy::new(|| {
-    reqwest::ClientBuilder::new()
-        .pool_idle_timeout(Duration::from_secs(300))  
-        .pool_max_idle_per_host(20)                   
-        .timeout(Duration::from_secs(30))
-        .connect_timeout(Duration::from_secs(10))
-        .tcp_keepalive(Duration::from_secs(30))       
-        .http2_keep_alive_interval(Duration::from_secs(30))  
-        .http2_keep_alive_timeout(Duration::from_secs(10))   
-        .http2_keep_alive_while_idle(true)            
-        .user_agent("solana-rpc-monitor/1.0")
-        .build()
-        .expect("Failed to create HTTP client")
-});
-
-// HTTP/1.1 only client for comparison
-static HTTP1_CLIENT: Lazy<Client> = Lazy::new(|| {
-    reqwest::ClientBuilder::new()
-        .http1_only()                                 
+use futures::future::join_all;
+use tokio::task;
+
+// Defaults used when an endpoint doesn't override its timeouts
+const DEFAULT_TIMEOUT_MS: u64 = 30_000;
+pub const DEFAULT_CONNECT_TIMEOUT_MS: u64 = 10_000;
+
+// Per-endpoint HTTP clients with connection pooling, keyed by (nickname, protocol)
+static CLIENTS: Lazy<Mutex<HashMap<(String, HttpProtocol), Client>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn endpoint_timeout(endpoint: &RpcEndpoint) -> Duration {
+    Duration::from_millis(endpoint.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS))
+}
+
+fn build_client(endpoint: &RpcEndpoint, protocol: HttpProtocol) -> Result<Client, String> {
+    let connect_timeout = endpoint.connect_timeout_ms.unwrap_or(DEFAULT_CONNECT_TIMEOUT_MS);
+
+    let mut builder = reqwest::ClientBuilder::new()
         .pool_idle_timeout(Duration::from_secs(300))
         .pool_max_idle_per_host(20)
-        .timeout(Duration::from_secs(30))
-        .connect_timeout(Duration::from_secs(10))
+        .timeout(endpoint_timeout(endpoint))
+        .connect_timeout(Duration::from_millis(connect_timeout))
         .tcp_keepalive(Duration::from_secs(30))
-        .user_agent("solana-rpc-monitor/1.0")
-        .build()
-        .expect("Failed to create HTTP/1.1 client")
-});
+        .user_agent("solana-rpc-monitor/1.0");
+
+    if let Some(ip) = endpoint.resolve_ip {
+        let url = reqwest::Url::parse(&endpoint.url).map_err(|e| format!("Invalid endpoint URL: {}", e))?;
+        let host = url
+            .host_str()
+            .ok_or_else(|| format!("Endpoint URL has no host: {}", endpoint.url))?;
+        // The port is ignored by reqwest; the URL's port (or the scheme default) is used
+        builder = builder.resolve(host, SocketAddr::new(ip, 0));
+    }
+
+    if let Some(proxy) = &endpoint.proxy {
+        let proxy = reqwest::Proxy::all(proxy).map_err(|e| format!("Invalid proxy {}: {}", proxy, e))?;
+        builder = builder.proxy(proxy);
+    }
+
+    if let Some(path) = &endpoint.ca_cert {
+        for cert in load_ca_bundle(path)? {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+
+    if let Some(path) = &endpoint.client_cert {
+        let mut pem = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        if let Some(key_path) = &endpoint.client_key {
+            let key = std::fs::read(key_path).map_err(|e| format!("Failed to read {}: {}", key_path, e))?;
+            pem.push(b'\n');
+            pem.extend_from_slice(&key);
+        }
+        let identity = reqwest::Identity::from_pem(&pem).map_err(|e| format!("Invalid client certificate {}: {}", path, e))?;
+        builder = builder.identity(identity);
+    }
+
+    builder = match protocol {
+        // HTTP/1.1 only client for comparison
+        HttpProtocol::Http1 => builder.http1_only(),
+        HttpProtocol::Http2 => builder
+            .http2_prior_knowledge()
+            .http2_keep_alive_interval(Duration::from_secs(30))
+            .http2_keep_alive_timeout(Duration::from_secs(10))
+            .http2_keep_alive_while_idle(true),
+        HttpProtocol::Auto => builder
+            .http2_keep_alive_interval(Duration::from_secs(30))
+            .http2_keep_alive_timeout(Duration::from_secs(10))
+            .http2_keep_alive_while_idle(true),
+    };
+
+    builder.build().map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+// Split a PEM bundle into individual certificates
+fn load_ca_bundle(path: &str) -> Result<Vec<reqwest::Certificate>, String> {
+    const BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+
+    let pem = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let certs = pem
+        .split(BEGIN)
+        .skip(1)
+        .map(|block| {
+            reqwest::Certificate::from_pem(format!("{}{}", BEGIN, block).as_bytes())
+                .map_err(|e| format!("Invalid CA certificate in {}: {}", path, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if certs.is_empty() {
+        return Err(format!("No certificates found in {}", path));
+    }
+    Ok(certs)
+}
+
+fn client_for(endpoint: &RpcEndpoint, protocol: HttpProtocol) -> Result<Client, String> {
+    let key = (endpoint.nickname.clone(), protocol);
+    let mut clients = CLIENTS.lock().unwrap();
+    if let Some(client) = clients.get(&key) {
+        return Ok(client.clone());
+    }
+
+    let client = build_client(endpoint, protocol)?;
+    clients.insert(key, client.clone());
+    Ok(client)
+}
+
+// A failed probe call: a sanitized summary that is safe to publish, plus the raw detail
+// (status codes, provider error bodies, URLs) that is only exposed to admins
+#[derive(Debug, Clone)]
+pub struct CallError {
+    pub summary: String,
+    pub detail: String,
+    /// Retry-After (in seconds) sent with an HTTP 429
+    pub retry_after: Option<u64>,
+}
+
+impl CallError {
+    pub fn new(summary: impl Into<String>, detail: impl Into<String>) -> Self {
+        CallError {
+            summary: summary.into(),
+            detail: detail.into(),
+            retry_after: None,
+        }
+    }
+
+    pub fn is_throttled(&self) -> bool {
+        self.summary == "HTTP 429"
+    }
+
+    // Resets mid-request surface from reqwest as generic request failures
+    fn class(&self) -> Option<RetryClass> {
+        match self.summary.as_str() {
+            "timeout" => Some(RetryClass::Timeout),
+            "connection failed" | "request failed" => Some(RetryClass::Connection),
+            summary if summary.starts_with("HTTP 5") => Some(RetryClass::Http5xx),
+            _ => None,
+        }
+    }
+
+    fn from_reqwest(e: reqwest::Error) -> Self {
+        let summary = if e.is_timeout() {
+            "timeout"
+        } else if e.is_connect() {
+            "connection failed"
+        } else {
+            "request failed"
+        };
+        CallError::new(summary, e.to_string())
+    }
+}
+
+impl std::fmt::Display for CallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.detail)
+    }
+}
+
+impl From<String> for CallError {
+    fn from(detail: String) -> Self {
+        CallError::new("client error", detail)
+    }
+}
+
+// Keep stored provider error bodies bounded
+fn truncate_body(body: &str) -> String {
+    body.chars().take(2048).collect()
+}
+
+// Timing and transport details captured for a single JSON-RPC call
+#[derive(Debug, Clone)]
+struct CallInfo {
+    latency_ms: u128,
+    protocol: String,
+    remote_ip: Option<String>,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct JsonRpcRequest {
@@ -65,9 +209,13 @@ struct JsonRpcResponse<T> {
 struct JsonRpcError {
     code: i32,
     message: String,
+    #[serde(default)]
+    data: Option<Value>,
 }
 
-async fn rpc_call_with_precise_timing<T>(url: &str, method: &str, params: Option<Value>, prefer_http2: bool) -> Result<(T, u128), String>
+// Send a timed request and return the JSON-RPC envelope as-is, so callers that
+// care about the error object (e.g. getHealth) still get the timing
+async fn rpc_call_raw<T>(endpoint: &RpcEndpoint, method: &str, params: Option<Value>, protocol: HttpProtocol) -> Result<(JsonRpcResponse<T>, CallInfo), CallError>
 where
     T: for<'de> Deserialize<'de>,
 {
@@ -78,61 +226,161 @@ where
         params,
     };
 
-    let client = if prefer_http2 {
-        &HTTP_CLIENT
-    } else {
-        &HTTP1_CLIENT
-    };
+    let client = client_for(endpoint, protocol)?;
 
     // Pre-serialize to avoid timing serialization overhead
     let request_body = serde_json::to_string(&request).map_err(|e| e.to_string())?;
 
+    usage::record_requests(&endpoint.nickname, 1);
+
     // Measure ONLY the network round trip (like OpenResty does)
     let precise_start = Instant::now();
     let response = client
-        .post(url)
+        .post(&endpoint.url)
         .header("Content-Type", "application/json")
         .body(request_body)  // Use pre-serialized body
         .send()
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(CallError::from_reqwest)?;
     
     // Stop timing immediately after response received
     let precise_latency = precise_start.elapsed().as_millis();
+    let call_info = CallInfo {
+        latency_ms: precise_latency,
+        protocol: format!("{:?}", response.version()),
+        remote_ip: response.remote_addr().map(|addr| addr.ip().to_string()),
+    };
 
-    if !response.status().is_success() {
-        return Err(format!("HTTP error: {}", response.status()));
+    let status = response.status();
+    if !status.is_success() {
+        // Only the delta-seconds form of Retry-After is used by RPC providers in practice
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok());
+        let body = response.text().await.unwrap_or_default();
+        return Err(CallError {
+            retry_after,
+            ..CallError::new(
+                format!("HTTP {}", status.as_u16()),
+                format!("HTTP error: {}; body: {}", status, truncate_body(&body)),
+            )
+        });
     }
 
     // Parse JSON outside of timing measurement
-    let response_text = response.text().await.map_err(|e| e.to_string())?;
-    let rpc_response: JsonRpcResponse<T> = serde_json::from_str(&response_text).map_err(|e| e.to_string())?;
+    let response_text = response.text().await.map_err(CallError::from_reqwest)?;
+    let rpc_response: JsonRpcResponse<T> = serde_json::from_str(&response_text).map_err(|e| {
+        CallError::new(
+            "invalid JSON-RPC response",
+            format!("{}; body: {}", e, truncate_body(&response_text)),
+        )
+    })?;
+
+    Ok((rpc_response, call_info))
+}
+
+// Retry retryable failures per the endpoint's policy. The returned timing is that of the
+// attempt that succeeded, so retries never inflate latency; they are counted in the
+// endpoint's protocol stats instead.
+async fn rpc_call_with_retries<T>(endpoint: &RpcEndpoint, method: &str, params: Option<Value>, protocol: HttpProtocol) -> Result<(JsonRpcResponse<T>, CallInfo), CallError>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    let policy = match &endpoint.retry {
+        Some(policy) => policy,
+        None => return rpc_call_raw(endpoint, method, params, protocol).await,
+    };
+
+    let mut attempt = 1;
+    loop {
+        match rpc_call_raw(endpoint, method, params.clone(), protocol).await {
+            Err(e) if attempt < policy.max_attempts() && e.class().map_or(false, |class| policy.retry_on.contains(&class)) => {
+                let backoff = policy.backoff_ms().saturating_mul(1u64 << (attempt - 1).min(16));
+                log_info!("[{}] {} failed ({}), retrying in {}ms", endpoint.nickname, method, e.summary, backoff);
+                protocol_stats::record_retry(&endpoint.nickname);
+                tokio::time::sleep(Duration::from_millis(backoff)).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+async fn rpc_call_with_precise_timing<T>(endpoint: &RpcEndpoint, method: &str, params: Option<Value>, protocol: HttpProtocol) -> Result<(T, CallInfo), CallError>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    let (rpc_response, call_info) = rpc_call_with_retries::<T>(endpoint, method, params, protocol).await?;
 
     if let Some(error) = rpc_response.error {
-        return Err(format!("RPC error {}: {}", error.code, error.message));
+        return Err(CallError::new(
+            format!("RPC error {}", error.code),
+            format!("RPC error {}: {}", error.code, error.message),
+        ));
     }
 
     let result = rpc_response
         .result
-        .ok_or_else(|| "Missing result in RPC response".to_string())?;
+        .ok_or_else(|| CallError::new("missing result", "Missing result in RPC response"))?;
+
+    Ok((result, call_info))
+}
+
+// Time a single call of an arbitrary method using the endpoint's configured protocol
+pub async fn time_method(endpoint: &RpcEndpoint, method: &str, params: Option<Value>) -> Result<u128, CallError> {
+    let (_result, call_info): (Value, CallInfo) =
+        rpc_call_with_precise_timing(endpoint, method, params, endpoint.protocol).await?;
+    Ok(call_info.latency_ms)
+}
+
+// Solana reports an unhealthy node as a JSON-RPC error (-32005) with
+// `{"numSlotsBehind": n}` in the data when it knows how far behind it is
+const NODE_UNHEALTHY: i32 = -32005;
 
-    Ok((result, precise_latency))
+fn parse_health(response: JsonRpcResponse<Value>) -> Result<HealthStatus, CallError> {
+    match response.error {
+        None => Ok(HealthStatus::Healthy),
+        Some(error) if error.code == NODE_UNHEALTHY => Ok(HealthStatus::Unhealthy {
+            num_slots_behind: error
+                .data
+                .as_ref()
+                .and_then(|data| data.get("numSlotsBehind"))
+                .and_then(Value::as_u64),
+            message: error.message,
+        }),
+        Some(error) => Err(CallError::new(
+            format!("RPC error {}", error.code),
+            format!("RPC error {}: {}", error.code, error.message),
+        )),
+    }
+}
+
+// Call an arbitrary method using the endpoint's configured protocol, returning the result and latency
+pub async fn call_method<T>(endpoint: &RpcEndpoint, method: &str, params: Option<Value>) -> Result<(T, u128), CallError>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    let (result, call_info) = rpc_call_with_precise_timing(endpoint, method, params, endpoint.protocol).await?;
+    Ok((result, call_info.latency_ms))
 }
 
 // Version that makes individual timed requests instead of concurrent
-async fn get_single_request_timing(url: &str, prefer_http2: bool) -> Result<u128, String> {
-    // Just measure a single getHealth call to get pure network timing
-    let (_result, timing): (Value, u128) = rpc_call_with_precise_timing(
-        url,
+async fn get_single_request_timing(endpoint: &RpcEndpoint, protocol: HttpProtocol) -> Result<(CallInfo, HealthStatus), CallError> {
+    // A single getHealth call gives pure network timing, and its answer is the node's own health verdict.
+    // Retries follow the endpoint's policy; the timing is the attempt that answered, never the sum.
+    let (response, timing): (JsonRpcResponse<Value>, CallInfo) = rpc_call_with_retries(
+        endpoint,
         "getHealth",
         None,
-        prefer_http2,
+        protocol,
     ).await?;
     
-    Ok(timing)
+    Ok((timing, parse_health(response)?))
 }
 
-async fn get_latest_blockhash_http2(url: &str, prefer_http2: bool) -> Result<(String, u128), String> {
+async fn get_latest_blockhash_http2(endpoint: &RpcEndpoint, protocol: HttpProtocol) -> Result<(String, u128), CallError> {
     #[derive(Deserialize)]
     struct BlockhashResponse {
         value: BlockhashValue,
@@ -143,139 +391,449 @@ async fn get_latest_blockhash_http2(url: &str, prefer_http2: bool) -> Result<(St
         blockhash: String,
     }
 
-    let (response, network_latency): (BlockhashResponse, u128) = rpc_call_with_precise_timing(
-        url,
+    let (response, call_info): (BlockhashResponse, CallInfo) = rpc_call_with_precise_timing(
+        endpoint,
         "getLatestBlockhash",
         Some(json!([{"commitment": "finalized"}])),
-        prefer_http2,
+        protocol,
     )
     .await?;
 
-    Ok((response.value.blockhash, network_latency))
+    Ok((response.value.blockhash, call_info.latency_ms))
 }
 
-async fn get_slot_http2(url: &str, prefer_http2: bool) -> Result<(u64, u128), String> {
-    let (slot, network_latency): (u64, u128) = rpc_call_with_precise_timing(
-        url,
+async fn get_slot_http2(endpoint: &RpcEndpoint, protocol: HttpProtocol) -> Result<(u64, u128), CallError> {
+    let (slot, call_info): (u64, CallInfo) = rpc_call_with_precise_timing(
+        endpoint,
         "getSlot",
         Some(json!([{"commitment": "finalized"}])),
-        prefer_http2,
+        protocol,
     )
     .await?;
 
-    Ok((slot, network_latency))
+    Ok((slot, call_info.latency_ms))
 }
 
-async fn fetch_both_http2(url: &str, prefer_http2: bool) -> Result<(String, u64, u128), String> {
-    // Make both requests concurrently using the same connection pool
-    let (blockhash_result, slot_result) = tokio::join!(
-        get_latest_blockhash_http2(url, prefer_http2),
-        get_slot_http2(url, prefer_http2)
+async fn get_block_height_http2(endpoint: &RpcEndpoint, protocol: HttpProtocol) -> Result<u64, CallError> {
+    let (block_height, _call_info): (u64, CallInfo) = rpc_call_with_precise_timing(
+        endpoint,
+        "getBlockHeight",
+        Some(json!([{"commitment": "finalized"}])),
+        protocol,
+    )
+    .await?;
+
+    Ok(block_height)
+}
+
+// A sample's chain position: slot plus block height when the endpoint provided it
+type Position = (u64, Option<u64>);
+
+async fn fetch_both_http2(endpoint: &RpcEndpoint, protocol: HttpProtocol) -> Result<(String, Position, u128), CallError> {
+    // Make all requests concurrently using the same connection pool
+    let (blockhash_result, slot_result, block_height_result) = tokio::join!(
+        get_latest_blockhash_http2(endpoint, protocol),
+        get_slot_http2(endpoint, protocol),
+        get_block_height_http2(endpoint, protocol)
     );
 
     let (blockhash, blockhash_latency) = blockhash_result?;
     let (slot, slot_latency) = slot_result?;
+    // Block height is supplementary; a failure here doesn't fail the sample
+    let block_height = block_height_result.ok();
 
     // Since requests run concurrently, the effective latency is the maximum of the two
     let effective_latency = std::cmp::max(blockhash_latency, slot_latency);
 
-    Ok((blockhash, slot, effective_latency))
+    Ok((blockhash, (slot, block_height), effective_latency))
 }
 
-// Enhanced function with HTTP/2 connection reuse and OpenResty-accurate timing
-pub async fn fetch_blockhash_and_slot(
-    endpoint: RpcEndpoint,
-    db: Arc<DB>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    
-    // Strategy: Get the data we need, but measure timing separately to match OpenResty
-    let (blockhash, slot) = match fetch_both_http2(&endpoint.url, true).await {
-        Ok((hash, slot_num, _)) => {  // Ignore the internal timing
-            HTTP2_REQUESTS.fetch_add(1, Ordering::Relaxed);
-            (hash, slot_num)
+// Auto mode: HTTP/2 first, then HTTP/1.1, then the legacy solana_client
+async fn fetch_with_fallback(endpoint: &RpcEndpoint, errors: &mut Vec<CallError>) -> (String, Position) {
+    match fetch_both_http2(endpoint, HttpProtocol::Auto).await {
+        Ok((hash, position, _)) => {  // Ignore the internal timing
+            protocol_stats::record(&endpoint.nickname, Outcome::Http2);
+            (hash, position)
         }
         Err(e) => {
             // Try HTTP/1.1 with connection reuse
-            match fetch_both_http2(&endpoint.url, false).await {
-                Ok((hash, slot_num, _)) => {  // Ignore the internal timing
-                    if HTTP2_REQUESTS.load(Ordering::Relaxed) < 5 {
-                        eprintln!("[{}] HTTP/2 failed, using HTTP/1.1: {}", endpoint.nickname, e);
+            match fetch_both_http2(endpoint, HttpProtocol::Http1).await {
+                Ok((hash, position, _)) => {  // Ignore the internal timing
+                    // Only the first few fallbacks per endpoint are logged; the rest show up in its stats
+                    if protocol_stats::get(&endpoint.nickname).http1_fallback < 5 {
+                        log_error!("[{}] HTTP/2 failed, using HTTP/1.1: {}", endpoint.nickname, e);
                     }
-                    FALLBACK_REQUESTS.fetch_add(1, Ordering::Relaxed);
-                    (hash, slot_num)
+                    errors.push(e);
+                    protocol_stats::record(&endpoint.nickname, Outcome::Http1Fallback);
+                    (hash, position)
                 }
-                Err(_) => {
+                Err(http1_error) => {
+                    errors.push(e);
+                    errors.push(http1_error);
                     // Final fallback to original solana_client
-                    eprintln!("[{}] Both HTTP/2 and HTTP/1.1 failed, using legacy client", endpoint.nickname);
+                    log_error!("[{}] Both HTTP/2 and HTTP/1.1 failed, using legacy client", endpoint.nickname);
                     
-                    let client = RpcClient::new(endpoint.url.clone());
+                    // The nonblocking client keeps a hung endpoint from tying up a tokio worker,
+                    // and the three calls run concurrently like the HTTP/2 path
+                    let client = RpcClient::new_with_timeout(endpoint.url.clone(), endpoint_timeout(endpoint));
+                    usage::record_requests(&endpoint.nickname, 3);
+                    let (blockhash_result, slot_result, block_height_result) = tokio::join!(
+                        client.get_latest_blockhash(),
+                        client.get_slot(),
+                        client.get_block_height()
+                    );
                     
-                    let blockhash = match client.get_latest_blockhash() {
+                    let blockhash = match blockhash_result {
                         Ok(hash) => hash.to_string(),
-                        Err(_) => "Unavailable".to_string(),
+                        Err(e) => {
+                            errors.push(CallError::new("legacy client failed", e.to_string()));
+                            "Unavailable".to_string()
+                        }
                     };
                     
-                    let slot = match client.get_slot() {
+                    let slot = match slot_result {
                         Ok(slot) => slot,
-                        Err(_) => {
-                            println!(
+                        Err(e) => {
+                            errors.push(CallError::new("legacy client failed", e.to_string()));
+                            log_info!(
                                 "Error fetching slot from {}: request failed",
                                 endpoint.nickname
                             );
                             0
                         }
                     };
+                    let block_height = block_height_result.ok();
+                    let outcome = if slot == 0 || blockhash == "Unavailable" {
+                        Outcome::Failed
+                    } else {
+                        Outcome::LegacyFallback
+                    };
+                    protocol_stats::record(&endpoint.nickname, outcome);
                     
-                    (blockhash, slot)
+                    (blockhash, (slot, block_height))
                 }
             }
         }
+    }
+}
+
+// Pinned mode: only the configured protocol is used, failures are not retried elsewhere
+async fn fetch_pinned(endpoint: &RpcEndpoint, protocol: HttpProtocol, errors: &mut Vec<CallError>) -> (String, Position) {
+    match fetch_both_http2(endpoint, protocol).await {
+        Ok((hash, position, _)) => {
+            let outcome = if protocol == HttpProtocol::Http2 {
+                Outcome::Http2
+            } else {
+                Outcome::Http1
+            };
+            protocol_stats::record(&endpoint.nickname, outcome);
+            (hash, position)
+        }
+        Err(e) => {
+            log_error!("[{}] {:?} request failed: {}", endpoint.nickname, protocol, e);
+            protocol_stats::record(&endpoint.nickname, Outcome::Failed);
+            errors.push(e);
+            ("Unavailable".to_string(), (0, None))
+        }
+    }
+}
+
+// Time getHealth over HTTP/2 and HTTP/1.1 at the same moment. Failures here only leave the
+// comparison empty; they say nothing about the sample itself.
+async fn compare_protocols(endpoint: &RpcEndpoint) -> (Option<u128>, Option<u128>) {
+    let (http2, http1) = tokio::join!(
+        get_single_request_timing(endpoint, HttpProtocol::Http2),
+        get_single_request_timing(endpoint, HttpProtocol::Http1)
+    );
+    let latency = |result: Result<(CallInfo, HealthStatus), CallError>, protocol: &str| match result {
+        Ok((info, _)) => Some(info.latency_ms),
+        Err(e) => {
+            log_error!("[{}] {} comparison timing failed: {}", endpoint.nickname, protocol, e);
+            None
+        }
+    };
+    (latency(http2, "HTTP/2"), latency(http1, "HTTP/1.1"))
+}
+
+pub async fn probe_endpoint(endpoint: &RpcEndpoint) -> RPCResponse {
+    probe_endpoint_detailed(endpoint).await.0
+}
+
+// Enhanced function with HTTP/2 connection reuse and OpenResty-accurate timing.
+// Also returns every call error encountered, including ones a fallback recovered from.
+pub async fn probe_endpoint_detailed(endpoint: &RpcEndpoint) -> (RPCResponse, Vec<CallError>) {
+    if endpoint.kind == EndpointKind::Jito {
+        return probe_jito(endpoint).await;
+    }
+    let mut errors = Vec::new();
+    
+    // Strategy: Get the data we need, but measure timing separately to match OpenResty
+    let (blockhash, (slot, block_height)) = match endpoint.protocol {
+        HttpProtocol::Auto => fetch_with_fallback(endpoint, &mut errors).await,
+        pinned => fetch_pinned(endpoint, pinned, &mut errors).await,
     };
     
     // Get a separate, precise timing measurement that matches OpenResty
-    let latency = match get_single_request_timing(&endpoint.url, true).await {
-        Ok(precise_timing) => precise_timing,
-        Err(_) => {
-            // Fallback timing measurement
-            match get_single_request_timing(&endpoint.url, false).await {
-                Ok(timing) => timing,
-                Err(_) => 1, // Default fallback
+    let timing = match endpoint.protocol {
+        HttpProtocol::Auto => match get_single_request_timing(endpoint, HttpProtocol::Auto).await {
+            Ok(precise_timing) => Some(precise_timing),
+            Err(e) => {
+                errors.push(e);
+                // Fallback timing measurement
+                get_single_request_timing(endpoint, HttpProtocol::Http1)
+                    .await
+                    .map_err(|e| errors.push(e))
+                    .ok()
             }
-        }
+        },
+        pinned => get_single_request_timing(endpoint, pinned)
+            .await
+            .map_err(|e| errors.push(e))
+            .ok(),
+    };
+    let degraded = timing.is_none() || slot == 0 || blockhash == "Unavailable";
+    let (latency, protocol, remote_ip, health) = match timing {
+        Some((info, health)) => (info.latency_ms, Some(info.protocol), info.remote_ip, Some(health)),
+        None => (1, None, None, None), // Default fallback
+    };
+    let (http2_latency_ms, http1_latency_ms) = if endpoint.compare_protocols.unwrap_or(false) {
+        compare_protocols(endpoint).await
+    } else {
+        (None, None)
     };
-    
-    // Log connection stats every 50 requests
-    let total_requests = HTTP2_REQUESTS.load(Ordering::Relaxed) + FALLBACK_REQUESTS.load(Ordering::Relaxed);
-    if total_requests % 50 == 0 && total_requests > 0 {
-        let http2_ratio = (HTTP2_REQUESTS.load(Ordering::Relaxed) * 100) / total_requests;
-        println!("Protocol stats: {}% HTTP/2, {}% HTTP/1.1+Legacy ({} total) [{}]", 
-            http2_ratio, 
-            100 - http2_ratio,
-            total_requests,
-            endpoint.nickname
-        );
-    }
     
     let response = RPCResponse {
-        timestamp: std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs_f64(),
+        timestamp_ms: chrono::Utc::now().timestamp_millis(),
         slot,
-        blockhash: blockhash.clone(),
+        blockhash,
         latency_ms: latency,
         rpc_url: endpoint.url.clone(),
         nickname: endpoint.nickname.clone(),
+        protocol,
+        remote_ip,
+        region: None,
+        source: None,
+        // Only the sanitized summary is attached to the (public) sample
+        error: if degraded {
+            errors.first().map(|e| e.summary.clone())
+        } else {
+            None
+        },
+        resolution: None,
+        health,
+        block_height,
+        throttled: errors.iter().any(CallError::is_throttled),
+        retry_after_secs: errors.iter().find_map(|e| e.retry_after),
+        tags: endpoint.tags.clone(),
+        http2_latency_ms,
+        http1_latency_ms,
+        kind: EndpointKind::Rpc,
+        round: None,
+        sample_count: None,
+        throttled_count: None,
     };
-    
-    let key = format!("{}:{}", endpoint.nickname, Utc::now().timestamp());
-    let value = serde_json::to_string(&response)?;
-    db.put(key.as_bytes(), value.as_bytes())?;
-    
-    println!(
+
+    (response, errors)
+}
+
+// Block engines and relayers answer JSON-RPC on their bundles path but hold no chain state.
+// `getTipAccounts` needs no auth and submits nothing, so its round trip is the sample's latency.
+async fn probe_jito(endpoint: &RpcEndpoint) -> (RPCResponse, Vec<CallError>) {
+    let mut errors = Vec::new();
+    let call = |protocol| rpc_call_with_precise_timing::<Vec<String>>(endpoint, "getTipAccounts", Some(json!([])), protocol);
+
+    let info = match call(endpoint.protocol).await {
+        Ok((_, info)) => {
+            let outcome = if endpoint.protocol == HttpProtocol::Http1 {
+                Outcome::Http1
+            } else {
+                Outcome::Http2
+            };
+            protocol_stats::record(&endpoint.nickname, outcome);
+            Some(info)
+        }
+        Err(e) if endpoint.protocol == HttpProtocol::Auto => {
+            errors.push(e);
+            match call(HttpProtocol::Http1).await {
+                Ok((_, info)) => {
+                    protocol_stats::record(&endpoint.nickname, Outcome::Http1Fallback);
+                    Some(info)
+                }
+                Err(e) => {
+                    errors.push(e);
+                    protocol_stats::record(&endpoint.nickname, Outcome::Failed);
+                    None
+                }
+            }
+        }
+        Err(e) => {
+            errors.push(e);
+            protocol_stats::record(&endpoint.nickname, Outcome::Failed);
+            None
+        }
+    };
+    let (latency, protocol, remote_ip) = match info {
+        Some(info) => (info.latency_ms, Some(info.protocol), info.remote_ip),
+        None => (1, None, None),
+    };
+
+    let response = RPCResponse {
+        timestamp_ms: chrono::Utc::now().timestamp_millis(),
+        slot: 0,
+        blockhash: String::new(),
+        latency_ms: latency,
+        rpc_url: endpoint.url.clone(),
+        nickname: endpoint.nickname.clone(),
+        error: if protocol.is_none() {
+            errors.first().map(|e| e.summary.clone())
+        } else {
+            None
+        },
+        protocol,
+        remote_ip,
+        region: None,
+        source: None,
+        resolution: None,
+        health: None,
+        block_height: None,
+        throttled: errors.iter().any(CallError::is_throttled),
+        retry_after_secs: errors.iter().find_map(|e| e.retry_after),
+        tags: endpoint.tags.clone(),
+        http2_latency_ms: None,
+        http1_latency_ms: None,
+        kind: EndpointKind::Jito,
+        round: None,
+        sample_count: None,
+        throttled_count: None,
+    };
+
+    (response, errors)
+}
+
+// Print a probed sample
+pub fn log_sample(response: &RPCResponse) {
+    if response.kind == EndpointKind::Jito {
+        log_info!("[{}] Tip accounts ({}ms)", response.nickname, response.latency_ms);
+        return;
+    }
+    log_info!(
         "[{}] Slot: {}, Blockhash: {} ({}ms)",
-        endpoint.nickname, slot, blockhash, latency
+        response.nickname, response.slot, response.blockhash, response.latency_ms
     );
-    
+    if let Some(HealthStatus::Unhealthy { message, .. }) = &response.health {
+        log_error!("[{}] Node reports unhealthy: {}", response.nickname, message);
+    }
+}
+
+// Probe every endpoint concurrently and collect the samples
+pub async fn probe_round(endpoints: &[RpcEndpoint]) -> Vec<RPCResponse> {
+    let tasks: Vec<_> = endpoints
+        .iter()
+        .cloned()
+        .map(|endpoint| task::spawn(async move { probe_endpoint(&endpoint).await }))
+        .collect();
+
+    join_all(tasks)
+        .await
+        .into_iter()
+        .filter_map(|result| result.ok())
+        .collect()
+}
+
+// Samples are keyed as "<label>:<unix millis>", with ":<source>" appended for ingested
+// samples so they never overwrite the monitor's own sample from the same millisecond
+pub fn sample_key(response: &RPCResponse) -> String {
+    match &response.source {
+        Some(source) => format!("{}:{}:{}", response.label(), response.timestamp_ms, source),
+        None => format!("{}:{}", response.label(), response.timestamp_ms),
+    }
+}
+
+pub fn store_response(db: &Store, response: &RPCResponse) -> Result<(), Box<dyn std::error::Error>> {
+    let value = serde_json::to_string(response)?;
+    db.put(sample_key(response).as_bytes(), value.as_bytes())?;
+    anonymize::note_nickname(&response.nickname);
     Ok(())
 }
+
+/// Rewrite samples stored under "<label>:<unix seconds>" keys, from before timestamps were
+/// normalized, to their millisecond keys, along with the round indexes pointing at them.
+/// Runs once per database; later starts only check the marker.
+pub fn migrate_sample_keys(db: &Store) -> Result<(), Box<dyn std::error::Error>> {
+    if db.get(SAMPLE_KEYS_MS_KEY.as_bytes())?.is_some() {
+        return Ok(());
+    }
+
+    let mut batch = rocksdb::WriteBatch::default();
+    let mut renamed: HashMap<String, String> = HashMap::new();
+    for item in db.iterator(rocksdb::IteratorMode::Start) {
+        let (key, value) = item?;
+        if !is_sample_key(&key) {
+            continue;
+        }
+        let response = match serde_json::from_slice::<RPCResponse>(&value) {
+            Ok(response) => response,
+            Err(_) => continue,
+        };
+        let old_key = String::from_utf8_lossy(&key).into_owned();
+        let new_key = sample_key(&response);
+        if new_key != old_key {
+            batch.put(new_key.as_bytes(), serde_json::to_vec(&response)?);
+            batch.delete(&key);
+            renamed.insert(old_key, new_key);
+        }
+    }
+
+    for (key, mut record) in scan_prefix::<RoundRecord>(db, ROUNDS_PREFIX) {
+        if record.keys.iter().any(|key| renamed.contains_key(key)) {
+            for key in record.keys.iter_mut() {
+                if let Some(new_key) = renamed.get(key) {
+                    *key = new_key.clone();
+                }
+            }
+            batch.put(key, serde_json::to_vec(&record)?);
+        }
+    }
+
+    batch.put(SAMPLE_KEYS_MS_KEY.as_bytes(), b"1");
+    db.write(batch)?;
+    if !renamed.is_empty() {
+        log_info!("🔑 Rewrote {} sample keys from unix seconds to milliseconds", renamed.len());
+    }
+    Ok(())
+}
+
+// Raw error details go to a separate, admin-only keyspace
+pub fn store_errors(db: &Store, response: &RPCResponse, errors: &[CallError]) -> Result<(), Box<dyn std::error::Error>> {
+    for (index, error) in errors.iter().enumerate() {
+        let record = ErrorRecord {
+            timestamp_ms: response.timestamp_ms,
+            nickname: response.nickname.clone(),
+            rpc_url: response.rpc_url.clone(),
+            summary: error.summary.clone(),
+            detail: error.detail.clone(),
+        };
+        let key = format!(
+            "{}{}:{}:{}",
+            ERRORS_PREFIX,
+            response.nickname,
+            response.timestamp_ms,
+            index
+        );
+        db.put(key.as_bytes(), serde_json::to_vec(&record)?)?;
+    }
+    Ok(())
+}
+
+pub async fn fetch_blockhash_and_slot(
+    endpoint: RpcEndpoint,
+    db: Arc<Store>,
+    round: u64,
+) -> Result<RPCResponse, Box<dyn std::error::Error>> {
+    let (mut response, errors) = probe_endpoint_detailed(&endpoint).await;
+    response.round = Some(round);
+    log_sample(&response);
+    store_errors(&db, &response, &errors)?;
+    store_response(&db, &response)?;
+    Ok(response)
+}
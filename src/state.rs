@@ -0,0 +1,18 @@
+use axum::extract::FromRef;
+use std::sync::Arc;
+
+use crate::config::AppConfig;
+use crate::storage::Store;
+
+/// Shared state handed to every axum handler
+#[derive(Clone)]
+pub struct AppState {
+    pub db: Arc<Store>,
+    pub config: Arc<AppConfig>,
+}
+
+impl FromRef<AppState> for Arc<Store> {
+    fn from_ref(state: &AppState) -> Self {
+        Arc::clone(&state.db)
+    }
+}
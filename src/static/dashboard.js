@@ -2,6 +2,7 @@
 let updating = false;
 let previousValues = {};
 let responseTimeChart;
+let heavyChart;
 let hiddenDatasets = {};
 
 const colors = [
@@ -15,6 +16,11 @@ const colors = [
 
 const nicknameColorMap = {};
 
+// Agent samples carry a probe region and are shown as separate series
+function seriesLabel(item) {
+    return item.region ? `${item.nickname}@${item.region}` : item.nickname;
+}
+
 function updateWithFade(elementId, value) {
     const element = document.getElementById(elementId);
     element.style.opacity = '0';
@@ -68,6 +74,51 @@ function updateYAxisMax() {
     responseTimeChart.update();
 }
 
+// Heavy calls run rarely and take far longer than the regular probes, so they get their own chart
+async function updateHeavyChart() {
+    const response = await fetch('/api/heavy?limit=1000');
+    const results = await response.json();
+    if (results.length === 0) return;
+    document.getElementById('heavyPanel').classList.remove('hidden');
+
+    const series = [...new Set(results.map(r => `${r.kind} · ${r.nickname}`))].sort();
+    const datasets = series.map((label, index) => {
+        const color = colors[index % colors.length];
+        return {
+            label,
+            data: results
+                .filter(r => `${r.kind} · ${r.nickname}` === label && r.latency_ms !== null)
+                .map(r => ({ x: new Date(r.timestamp_ms), y: r.latency_ms })),
+            borderColor: color,
+            backgroundColor: color.replace('1)', '0.2)'),
+            borderWidth: 2,
+            pointRadius: 3
+        };
+    });
+
+    if (heavyChart) {
+        heavyChart.data.datasets = datasets;
+        heavyChart.update();
+        return;
+    }
+    const ctx = document.getElementById('heavyChart').getContext('2d');
+    heavyChart = new Chart(ctx, {
+        type: 'line',
+        data: { datasets },
+        options: {
+            responsive: true,
+            maintainAspectRatio: false,
+            plugins: {
+                title: { display: true, text: 'Heavy Calls' }
+            },
+            scales: {
+                x: { type: 'time', title: { display: true, text: 'Time' } },
+                y: { title: { display: true, text: 'Response Time (ms)' }, beginAtZero: true }
+            }
+        }
+    });
+}
+
 async function fetchData() {
     if (updating) return;
     updating = true;
@@ -99,9 +150,9 @@ async function fetchData() {
             entry => `${entry.nickname}: ${entry.value}ms`,
             'Fastest Response Times');
         // Calculate average response time per endpoint from raw data
-        const nicknames = [...new Set(data.map(item => item.nickname))].sort();
+        const nicknames = [...new Set(data.map(seriesLabel))].sort();
         const avgResponseTimes = nicknames.map(nickname => {
-            const endpointData = data.filter(item => item.nickname === nickname);
+            const endpointData = data.filter(item => seriesLabel(item) === nickname);
             const totalLatency = endpointData.reduce((sum, item) => sum + item.latency_ms, 0);
             const avgLatency = endpointData.length > 0 ? totalLatency / endpointData.length : 0;
             return {
@@ -127,8 +178,8 @@ async function fetchData() {
             const color = nicknameColorMap[nickname];
             return {
                 label: nickname,
-                data: data.filter(item => item.nickname === nickname).map(item => ({
-                    x: new Date(item.timestamp * 1000),
+                data: data.filter(item => seriesLabel(item) === nickname).map(item => ({
+                    x: new Date(item.timestamp_ms),
                     y: item.latency_ms
                 })),
                 borderColor: color,
@@ -214,6 +265,8 @@ async function fetchData() {
             legend.appendChild(legendItem);
         });
 
+        await updateHeavyChart();
+
         document.getElementById('status').textContent = `Last updated: ${new Date().toLocaleTimeString()}`;
     } catch (error) {
         console.error('Error fetching data:', error);
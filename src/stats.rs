@@ -0,0 +1,44 @@
+use serde::Serialize;
+
+/// Nearest-rank percentile of an ascending-sorted slice
+pub fn percentile<T: Copy + Default>(sorted: &[T], p: f64) -> T {
+    if sorted.is_empty() {
+        return T::default();
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LatencySummary {
+    pub count: usize,
+    pub min: u128,
+    pub avg: f64,
+    pub p50: u128,
+    pub p95: u128,
+    pub p99: u128,
+    pub max: u128,
+}
+
+impl LatencySummary {
+    pub fn from_samples(samples: &[u128]) -> Self {
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+
+        let avg = if sorted.is_empty() {
+            0.0
+        } else {
+            sorted.iter().map(|&v| v as f64).sum::<f64>() / sorted.len() as f64
+        };
+
+        LatencySummary {
+            count: sorted.len(),
+            min: sorted.first().copied().unwrap_or(0),
+            avg,
+            p50: percentile(&sorted, 50.0),
+            p95: percentile(&sorted, 95.0),
+            p99: percentile(&sorted, 99.0),
+            max: sorted.last().copied().unwrap_or(0),
+        }
+    }
+}
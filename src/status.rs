@@ -0,0 +1,233 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use crate::access::{allowed, resolve_scope};
+use crate::check::sample_errors;
+use crate::config::StatusConfig;
+use crate::histograms;
+use crate::models::RPCResponse;
+use crate::state::AppState;
+
+// Window the p95 on /api/status is taken over
+pub const STATUS_P95_MINUTES: i64 = 5;
+
+/// Ordered from best to worst so transitions can be compared
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum EndpointState {
+    Healthy,
+    Degraded,
+    Down,
+    /// Probing stopped on purpose; never compared, since paused endpoints get no samples
+    Paused,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct EndpointStatus {
+    pub state: EndpointState,
+    /// Unix milliseconds of the last transition
+    pub since_ms: i64,
+    /// Why the endpoint left the healthy state; empty while healthy
+    pub reason: Option<String>,
+    pub consecutive_errors: u32,
+    /// Slots behind the highest endpoint in the latest sample
+    pub slot_lag: u64,
+    /// Unix milliseconds of the latest sample
+    pub last_checked_ms: i64,
+    #[serde(skip)]
+    worse_streak: u32,
+    #[serde(skip)]
+    better_streak: u32,
+}
+
+/// A change of state produced by one poll round
+#[derive(Debug, Clone)]
+pub struct Transition {
+    pub label: String,
+    pub timestamp_ms: i64,
+    pub from: EndpointState,
+    pub to: EndpointState,
+    pub reason: Option<String>,
+}
+
+impl EndpointStatus {
+    fn new(since_ms: i64) -> Self {
+        EndpointStatus {
+            state: EndpointState::Healthy,
+            since_ms,
+            reason: None,
+            consecutive_errors: 0,
+            slot_lag: 0,
+            last_checked_ms: since_ms,
+            worse_streak: 0,
+            better_streak: 0,
+        }
+    }
+}
+
+// Latest state per sample label, updated by the live poller
+static STATUSES: Lazy<Mutex<HashMap<String, EndpointStatus>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// The state a single sample points at, ignoring history
+fn classify(sample: &RPCResponse, slot_lag: u64, consecutive_errors: u32, config: &StatusConfig) -> (EndpointState, Option<String>) {
+    let errors = sample_errors(sample);
+    if consecutive_errors >= config.down_after_errors() {
+        return (EndpointState::Down, Some(format!("{} consecutive errors", consecutive_errors)));
+    }
+    if let Some(error) = errors.first() {
+        return (EndpointState::Degraded, Some(error.clone()));
+    }
+    if slot_lag > config.max_slot_lag() {
+        return (EndpointState::Degraded, Some(format!("{} slots behind", slot_lag)));
+    }
+    if sample.latency_ms > config.max_latency_ms() {
+        return (EndpointState::Degraded, Some(format!("latency {}ms", sample.latency_ms)));
+    }
+    (EndpointState::Healthy, None)
+}
+
+// Advance each endpoint's state machine with one poll round. Getting worse takes
+// `degrade_after` consecutive bad samples (going down is immediate once the error
+// threshold is hit), and recovering takes `recover_after` consecutive better ones.
+pub fn record_round(samples: &[RPCResponse], config: &StatusConfig) -> Vec<Transition> {
+    let highest_slot = samples.iter().map(|s| s.slot).max().unwrap_or(0);
+    let mut statuses = STATUSES.lock().unwrap();
+    let mut transitions = Vec::new();
+
+    for sample in samples {
+        let status = statuses
+            .entry(sample.label())
+            .or_insert_with(|| EndpointStatus::new(sample.timestamp_ms));
+
+        if sample_errors(sample).is_empty() {
+            status.consecutive_errors = 0;
+        } else {
+            status.consecutive_errors += 1;
+        }
+
+        let slot_lag = sample.slot_lag(highest_slot);
+        status.slot_lag = slot_lag;
+        status.last_checked_ms = sample.timestamp_ms;
+        let (target, reason) = classify(sample, slot_lag, status.consecutive_errors, config);
+
+        let transition = if target > status.state {
+            status.better_streak = 0;
+            status.worse_streak += 1;
+            target == EndpointState::Down || status.worse_streak >= config.degrade_after()
+        } else if target < status.state {
+            status.worse_streak = 0;
+            status.better_streak += 1;
+            status.better_streak >= config.recover_after()
+        } else {
+            status.worse_streak = 0;
+            status.better_streak = 0;
+            if reason.is_some() {
+                status.reason = reason.clone();
+            }
+            false
+        };
+
+        if transition {
+            log_info!("[{}] {:?} -> {:?}{}", sample.label(), status.state, target,
+                reason.as_ref().map(|r| format!(" ({})", r)).unwrap_or_default());
+            transitions.push(Transition {
+                label: sample.label(),
+                timestamp_ms: sample.timestamp_ms,
+                from: status.state,
+                to: target,
+                reason: reason.clone(),
+            });
+            status.state = target;
+            status.since_ms = sample.timestamp_ms;
+            status.reason = reason;
+            status.worse_streak = 0;
+            status.better_streak = 0;
+        }
+    }
+    transitions
+}
+
+/// Mark an endpoint paused, or bring it back as healthy so the next samples decide its state
+pub fn set_paused(label: &str, paused: bool) {
+    let now = chrono::Utc::now().timestamp_millis();
+    let mut statuses = STATUSES.lock().unwrap();
+    let status = statuses.entry(label.to_string()).or_insert_with(|| EndpointStatus::new(now));
+    let last_checked_ms = status.last_checked_ms;
+    if paused {
+        *status = EndpointStatus {
+            state: EndpointState::Paused,
+            reason: Some("paused".to_string()),
+            last_checked_ms,
+            ..EndpointStatus::new(now)
+        };
+    } else if status.state == EndpointState::Paused {
+        *status = EndpointStatus {
+            last_checked_ms,
+            ..EndpointStatus::new(now)
+        };
+    }
+}
+
+/// Current state of every endpoint seen by the poller, keyed by label
+pub fn snapshot() -> BTreeMap<String, EndpointStatus> {
+    STATUSES
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(label, status)| (label.clone(), status.clone()))
+        .collect()
+}
+
+/// Just enough of an endpoint's status for a status page widget
+#[derive(Debug, Serialize)]
+pub struct CompactStatus {
+    pub state: EndpointState,
+    pub since_ms: i64,
+    /// Over the last 5 minutes of successful samples; empty without any
+    pub p95_ms: Option<u64>,
+    pub slot_lag: u64,
+    pub last_checked_ms: i64,
+}
+
+// One small entry per endpoint label, for embedding in status pages and other dashboards
+pub async fn get_status(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<BTreeMap<String, CompactStatus>>, StatusCode> {
+    let scope = resolve_scope(&state, &headers)?;
+
+    let visible = |label: &str| {
+        let nickname = label.split('@').next().unwrap_or(label);
+        state
+            .config
+            .rpc
+            .endpoints
+            .iter()
+            .find(|e| e.nickname == nickname)
+            .map_or(scope.is_none(), |e| allowed(&scope, &e.nickname, &e.tags))
+    };
+
+    let statuses = snapshot()
+        .into_iter()
+        .filter(|(label, _)| visible(label))
+        .map(|(label, status)| {
+            let compact = CompactStatus {
+                state: status.state,
+                since_ms: status.since_ms,
+                p95_ms: histograms::recent_quantile(&state.db, &label, STATUS_P95_MINUTES, 0.95),
+                slot_lag: status.slot_lag,
+                last_checked_ms: status.last_checked_ms,
+            };
+            (label, compact)
+        })
+        .collect();
+
+    Ok(Json(statuses))
+}
@@ -0,0 +1,264 @@
+use rocksdb::{Direction, IteratorMode, WriteBatch, WriteBatchIterator, DB};
+use serde::de::DeserializeOwned;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::ops::Bound;
+use std::sync::Mutex;
+
+// Samples live under "<label>:<ts>" keys; everything else is namespaced under a
+// "!"-prefixed key so it sorts before the samples and is never mistaken for one.
+pub const ERRORS_PREFIX: &str = "!errors:";
+pub const AGG_1M_PREFIX: &str = "!agg1m:";
+pub const AGG_1H_PREFIX: &str = "!agg1h:";
+pub const CONFIRMATIONS_PREFIX: &str = "!confirm:";
+pub const PROBES_PREFIX: &str = "!probe:";
+pub const HEAVY_PREFIX: &str = "!heavy:";
+pub const INCIDENTS_PREFIX: &str = "!incident:";
+pub const USAGE_PREFIX: &str = "!usage:";
+pub const ALERTS_PREFIX: &str = "!alert:";
+pub const ANOMALIES_PREFIX: &str = "!anomaly:";
+pub const HISTOGRAMS_PREFIX: &str = "!hdr:";
+pub const PROTOCOL_STATS_PREFIX: &str = "!pstats:";
+pub const PAUSED_PREFIX: &str = "!paused:";
+pub const LEADER_ROUNDS_PREFIX: &str = "!leader:";
+pub const CLUSTER_EVENTS_PREFIX: &str = "!cluster:";
+pub const ROUNDS_PREFIX: &str = "!round:";
+pub const ROUND_COUNTER_KEY: &str = "!roundseq";
+// Set once sample keys written as "<label>:<unix seconds>" have been rewritten to milliseconds
+pub const SAMPLE_KEYS_MS_KEY: &str = "!samplekeys_ms";
+
+pub fn is_sample_key(key: &[u8]) -> bool {
+    !key.starts_with(b"!")
+}
+
+/// A key/value pair as returned by `Store::iterator`
+pub type StoreItem = Result<(Box<[u8]>, Box<[u8]>), rocksdb::Error>;
+
+/// The database: RocksDB on disk, or with `[storage] mode = "memory"` a sorted map that keeps
+/// only the newest `max_samples` raw samples, like a ring buffer, and never touches the disk
+pub enum Store {
+    Disk(DB),
+    Memory(MemoryStore),
+}
+
+impl Store {
+    pub fn get<K: AsRef<[u8]>>(&self, key: K) -> Result<Option<Vec<u8>>, rocksdb::Error> {
+        match self {
+            Store::Disk(db) => db.get(key),
+            Store::Memory(memory) => Ok(memory.inner.lock().unwrap().entries.get(key.as_ref()).cloned()),
+        }
+    }
+
+    pub fn put<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, key: K, value: V) -> Result<(), rocksdb::Error> {
+        match self {
+            Store::Disk(db) => db.put(key, value),
+            Store::Memory(memory) => {
+                memory.put(key.as_ref().to_vec(), value.as_ref().to_vec());
+                Ok(())
+            }
+        }
+    }
+
+    pub fn delete<K: AsRef<[u8]>>(&self, key: K) -> Result<(), rocksdb::Error> {
+        match self {
+            Store::Disk(db) => db.delete(key),
+            Store::Memory(memory) => {
+                memory.remove(key.as_ref());
+                Ok(())
+            }
+        }
+    }
+
+    pub fn write(&self, batch: WriteBatch) -> Result<(), rocksdb::Error> {
+        match self {
+            Store::Disk(db) => db.write(batch),
+            Store::Memory(memory) => {
+                let mut ops = BatchOps::default();
+                batch.iterate(&mut ops);
+                for (key, value) in ops.0 {
+                    match value {
+                        Some(value) => memory.put(key.into_vec(), value.into_vec()),
+                        None => memory.remove(&key),
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    pub fn iterator(&self, mode: IteratorMode) -> Box<dyn Iterator<Item = StoreItem> + '_> {
+        match self {
+            Store::Disk(db) => Box::new(db.iterator(mode)),
+            Store::Memory(memory) => {
+                let (position, direction) = match mode {
+                    IteratorMode::Start => (Bound::Unbounded, Direction::Forward),
+                    IteratorMode::End => (Bound::Unbounded, Direction::Reverse),
+                    IteratorMode::From(key, direction) => (Bound::Included(key.to_vec()), direction),
+                };
+                Box::new(MemoryIter { memory, position, direction })
+            }
+        }
+    }
+
+    pub fn flush(&self) -> Result<(), rocksdb::Error> {
+        match self {
+            Store::Disk(db) => db.flush(),
+            Store::Memory(_) => Ok(()),
+        }
+    }
+
+    /// The RocksDB instance, for checkpoints; None in memory mode
+    pub fn disk(&self) -> Option<&DB> {
+        match self {
+            Store::Disk(db) => Some(db),
+            Store::Memory(_) => None,
+        }
+    }
+}
+
+pub struct MemoryStore {
+    inner: Mutex<MemoryEntries>,
+    max_samples: usize,
+}
+
+#[derive(Default)]
+struct MemoryEntries {
+    entries: BTreeMap<Vec<u8>, Vec<u8>>,
+    // Raw sample keys with the generation they were inserted as, oldest first. Deleted keys
+    // stay queued until they reach the front; a key written again gets a new generation, so
+    // its old queue entry never evicts the live one.
+    samples: VecDeque<(Vec<u8>, u64)>,
+    // Generation of every stored raw sample key
+    live: HashMap<Vec<u8>, u64>,
+    next_generation: u64,
+}
+
+impl MemoryStore {
+    pub fn new(max_samples: usize) -> Self {
+        MemoryStore {
+            inner: Mutex::new(MemoryEntries::default()),
+            max_samples,
+        }
+    }
+
+    // Everything else is kept until retention removes it; raw samples are also capped here,
+    // evicting the oldest
+    fn put(&self, key: Vec<u8>, value: Vec<u8>) {
+        let mut guard = self.inner.lock().unwrap();
+        let inner = &mut *guard;
+        if inner.entries.insert(key.clone(), value).is_some() || !is_sample_key(&key) {
+            return;
+        }
+
+        let generation = inner.next_generation;
+        inner.next_generation += 1;
+        inner.live.insert(key.clone(), generation);
+        inner.samples.push_back((key, generation));
+
+        // Drop queue entries of deleted or rewritten keys as they surface, and evict the
+        // oldest live samples past the cap
+        while let Some((oldest, generation)) = inner.samples.front() {
+            let live = inner.live.get(oldest) == Some(generation);
+            if live && inner.live.len() <= self.max_samples {
+                break;
+            }
+            if let Some((oldest, _)) = inner.samples.pop_front() {
+                if live {
+                    inner.live.remove(&oldest);
+                    inner.entries.remove(&oldest);
+                }
+            }
+        }
+    }
+
+    fn remove(&self, key: &[u8]) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.entries.remove(key);
+        inner.live.remove(key);
+    }
+}
+
+// Walks the map one key at a time, so writers are never blocked for a whole scan
+struct MemoryIter<'a> {
+    memory: &'a MemoryStore,
+    position: Bound<Vec<u8>>,
+    direction: Direction,
+}
+
+impl Iterator for MemoryIter<'_> {
+    type Item = StoreItem;
+
+    fn next(&mut self) -> Option<StoreItem> {
+        let inner = self.memory.inner.lock().unwrap();
+        let (key, value) = match self.direction {
+            Direction::Forward => inner.entries.range((self.position.clone(), Bound::Unbounded)).next(),
+            Direction::Reverse => inner.entries.range((Bound::Unbounded, self.position.clone())).next_back(),
+        }?;
+        self.position = Bound::Excluded(key.clone());
+        Some(Ok((key.clone().into_boxed_slice(), value.clone().into_boxed_slice())))
+    }
+}
+
+// Puts (Some) and deletes (None) of a write batch, in order
+#[derive(Default)]
+struct BatchOps(Vec<(Box<[u8]>, Option<Box<[u8]>>)>);
+
+impl WriteBatchIterator for BatchOps {
+    fn put(&mut self, key: Box<[u8]>, value: Box<[u8]>) {
+        self.0.push((key, Some(value)));
+    }
+
+    fn delete(&mut self, key: Box<[u8]>) {
+        self.0.push((key, None));
+    }
+}
+
+/// All raw values under `prefix` in key order
+pub fn scan_prefix_raw(db: &Store, prefix: &str) -> Vec<(Vec<u8>, Vec<u8>)> {
+    db.iterator(IteratorMode::From(prefix.as_bytes(), Direction::Forward))
+        .filter_map(|item| item.ok())
+        .take_while(|(key, _)| key.starts_with(prefix.as_bytes()))
+        .map(|(key, value)| (key.to_vec(), value.to_vec()))
+        .collect()
+}
+
+/// All records under `prefix` in key order, skipping entries that fail to parse
+pub fn scan_prefix<T: DeserializeOwned>(db: &Store, prefix: &str) -> Vec<(Vec<u8>, T)> {
+    scan_prefix_raw(db, prefix)
+        .into_iter()
+        .filter_map(|(key, value)| {
+            serde_json::from_slice::<T>(&value)
+                .ok()
+                .map(|record| (key, record))
+        })
+        .collect()
+}
+
+/// The first record under `prefix` in key order
+pub fn first_in_prefix<T: DeserializeOwned>(db: &Store, prefix: &str) -> Option<(Vec<u8>, T)> {
+    db.iterator(IteratorMode::From(prefix.as_bytes(), Direction::Forward))
+        .filter_map(|item| item.ok())
+        .take_while(|(key, _)| key.starts_with(prefix.as_bytes()))
+        .find_map(|(key, value)| {
+            serde_json::from_slice::<T>(&value)
+                .ok()
+                .map(|record| (key.to_vec(), record))
+        })
+}
+
+/// The last record under `prefix` in key order, read by seeking backwards from just past
+/// the prefix instead of scanning all of it
+pub fn last_in_prefix<T: DeserializeOwned>(db: &Store, prefix: &str) -> Option<(Vec<u8>, T)> {
+    // Prefixes end in ':', so bumping the last byte gives a key that sorts right after them
+    let mut end = prefix.as_bytes().to_vec();
+    if let Some(last) = end.last_mut() {
+        *last += 1;
+    }
+    db.iterator(IteratorMode::From(&end, Direction::Reverse))
+        .filter_map(|item| item.ok())
+        .take_while(|(key, _)| key.starts_with(prefix.as_bytes()))
+        .find_map(|(key, value)| {
+            serde_json::from_slice::<T>(&value)
+                .ok()
+                .map(|record| (key.to_vec(), record))
+        })
+}
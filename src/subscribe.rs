@@ -0,0 +1,209 @@
+use futures_util::{SinkExt, StreamExt};
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::config::AccountSubscribeProbeConfig;
+use crate::models::RpcEndpoint;
+use crate::pause;
+use crate::probes::{store_results, ProbeResult};
+use crate::stats::percentile;
+use crate::storage::Store;
+use crate::usage;
+use crate::websocket;
+
+// Notifications for a slot are only scored once they are this old, so late arrivals still count
+const SETTLE_TIME: Duration = Duration::from_secs(5);
+
+// How often an open subscription checks whether it may stay connected
+const GATE_CHECK: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+struct Window {
+    // slot -> nickname -> when that endpoint's notification for the slot arrived
+    arrivals: HashMap<u64, HashMap<String, Instant>>,
+    connected: HashSet<String>,
+}
+
+static WINDOW: Lazy<Mutex<Window>> = Lazy::new(|| Mutex::new(Window::default()));
+
+// The endpoint's `ws_url`, or its HTTP URL with the scheme swapped
+fn ws_url(endpoint: &RpcEndpoint) -> String {
+    match &endpoint.ws_url {
+        Some(url) => url.clone(),
+        None => endpoint
+            .url
+            .replacen("https://", "wss://", 1)
+            .replacen("http://", "ws://", 1),
+    }
+}
+
+async fn stream_account(endpoint: &RpcEndpoint, account: &str, commitment: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut socket = websocket::connect(endpoint, &ws_url(endpoint)).await?;
+    let subscribe = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "accountSubscribe",
+        "params": [account, {"encoding": "base64", "commitment": commitment}]
+    });
+    socket.send(Message::Text(subscribe.to_string())).await?;
+    usage::record_requests(&endpoint.nickname, 1);
+    WINDOW.lock().unwrap().connected.insert(endpoint.nickname.clone());
+
+    let mut gate = tokio::time::interval(GATE_CHECK);
+    loop {
+        let message = tokio::select! {
+            message = socket.next() => match message {
+                Some(message) => message,
+                None => break,
+            },
+            _ = gate.tick() => {
+                if pause::is_paused(&endpoint.nickname) {
+                    log_info!("[{}] Paused, closing accountSubscribe stream", endpoint.nickname);
+                    let _ = socket.close(None).await;
+                    break;
+                }
+                if usage::budget_spent(endpoint) {
+                    log_info!("[{}] Daily request budget spent, closing accountSubscribe stream", endpoint.nickname);
+                    let _ = socket.close(None).await;
+                    break;
+                }
+                continue;
+            }
+        };
+        let text = match message? {
+            Message::Text(text) => text,
+            Message::Ping(payload) => {
+                socket.send(Message::Pong(payload)).await?;
+                continue;
+            }
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        let notification: Value = match serde_json::from_str(&text) {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+        if let Some(error) = notification.get("error") {
+            return Err(format!("subscription rejected: {}", error).into());
+        }
+        if let Some(slot) = notification.pointer("/params/result/context/slot").and_then(Value::as_u64) {
+            let arrived = Instant::now();
+            WINDOW
+                .lock()
+                .unwrap()
+                .arrivals
+                .entry(slot)
+                .or_default()
+                .entry(endpoint.nickname.clone())
+                .or_insert(arrived);
+        }
+    }
+    Ok(())
+}
+
+// Keep one subscription per endpoint alive, reconnecting after failures; paused endpoints
+// and those over their request budget stay disconnected
+fn spawn_subscriber(endpoint: RpcEndpoint, account: String, commitment: String) {
+    tokio::spawn(async move {
+        loop {
+            if !pause::is_paused(&endpoint.nickname) && usage::allow_probe(&endpoint) {
+                if let Err(e) = stream_account(&endpoint, &account, &commitment).await {
+                    log_error!("[{}] accountSubscribe stream failed: {}", endpoint.nickname, e);
+                }
+                WINDOW.lock().unwrap().connected.remove(&endpoint.nickname);
+            }
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+    });
+}
+
+// Score settled slots: each endpoint's delay behind the first notification for the slot,
+// and how many slots it never delivered while connected
+fn score_window(endpoints: &[RpcEndpoint]) -> Vec<ProbeResult> {
+    let mut window = WINDOW.lock().unwrap();
+    let settled: Vec<u64> = window
+        .arrivals
+        .iter()
+        .filter(|(_, arrivals)| arrivals.values().min().map_or(false, |first| first.elapsed() >= SETTLE_TIME))
+        .map(|(slot, _)| *slot)
+        .collect();
+
+    let mut deltas: HashMap<&str, Vec<u128>> = HashMap::new();
+    let mut missed: HashMap<&str, u64> = HashMap::new();
+    for slot in &settled {
+        let arrivals = window.arrivals.remove(slot).unwrap_or_default();
+        let first = match arrivals.values().min() {
+            Some(first) => *first,
+            None => continue,
+        };
+        for endpoint in endpoints {
+            match arrivals.get(&endpoint.nickname) {
+                Some(arrived) => deltas
+                    .entry(endpoint.nickname.as_str())
+                    .or_default()
+                    .push(arrived.duration_since(first).as_millis()),
+                None if window.connected.contains(&endpoint.nickname) => {
+                    *missed.entry(endpoint.nickname.as_str()).or_insert(0) += 1
+                }
+                None => {}
+            }
+        }
+    }
+
+    // Endpoints kept offline by a pause or their budget have nothing to score
+    endpoints
+        .iter()
+        .filter(|endpoint| {
+            window.connected.contains(&endpoint.nickname)
+                || !(pause::is_paused(&endpoint.nickname) || usage::budget_spent(endpoint))
+        })
+        .map(|endpoint| {
+            let result = ProbeResult::new("account_subscribe", endpoint);
+            let mut delays = deltas.remove(endpoint.nickname.as_str()).unwrap_or_default();
+            delays.sort_unstable();
+            let missed = missed.get(endpoint.nickname.as_str()).copied().unwrap_or(0);
+            let connected = window.connected.contains(&endpoint.nickname);
+            let average = (!delays.is_empty()).then(|| delays.iter().sum::<u128>() / delays.len() as u128);
+            ProbeResult {
+                latency_ms: average,
+                success: connected && !delays.is_empty(),
+                error: if !connected {
+                    Some("not connected".to_string())
+                } else if delays.is_empty() {
+                    Some("no notifications".to_string())
+                } else {
+                    None
+                },
+                detail: Some(json!({
+                    "notifications": delays.len(),
+                    "missed": missed,
+                    "p50_delta_ms": percentile(&delays, 50.0),
+                    "p95_delta_ms": percentile(&delays, 95.0),
+                    "max_delta_ms": delays.last(),
+                })),
+                ..result
+            }
+        })
+        .collect()
+}
+
+// Subscribe to the account on every endpoint and store a per-endpoint summary of
+// notification arrival deltas every `interval_secs`
+pub fn spawn_account_subscribe(config: &AccountSubscribeProbeConfig, endpoints: Vec<RpcEndpoint>, db: Arc<Store>) {
+    for endpoint in &endpoints {
+        spawn_subscriber(endpoint.clone(), config.account.clone(), config.commitment().to_string());
+    }
+
+    let interval = Duration::from_secs(config.interval_secs());
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+            let results = score_window(&endpoints);
+            store_results(&db, &results);
+        }
+    });
+}
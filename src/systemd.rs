@@ -0,0 +1,71 @@
+use std::os::unix::net::UnixDatagram;
+use std::time::Duration;
+
+use crate::liveness::seconds_since_progress;
+
+// Minimal sd_notify(3): send a state string to $NOTIFY_SOCKET if systemd gave us one
+pub fn notify(state: &str) {
+    let path = match std::env::var("NOTIFY_SOCKET") {
+        Ok(path) if !path.is_empty() => path,
+        _ => return,
+    };
+
+    let result = UnixDatagram::unbound().and_then(|socket| {
+        if let Some(name) = path.strip_prefix('@') {
+            send_abstract(&socket, name, state)
+        } else {
+            socket.send_to(state.as_bytes(), &path).map(|_| ())
+        }
+    });
+
+    if let Err(e) = result {
+        log_error!("Error notifying systemd ({}): {}", state, e);
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn send_abstract(socket: &UnixDatagram, name: &str, state: &str) -> std::io::Result<()> {
+    use std::os::linux::net::SocketAddrExt;
+    let addr = std::os::unix::net::SocketAddr::from_abstract_name(name.as_bytes())?;
+    socket.send_to_addr(state.as_bytes(), &addr).map(|_| ())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn send_abstract(_socket: &UnixDatagram, _name: &str, _state: &str) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "abstract notify sockets are Linux-only",
+    ))
+}
+
+// Watchdog interval requested by systemd via WatchdogSec=
+fn watchdog_interval() -> Option<Duration> {
+    let usec: u64 = std::env::var("WATCHDOG_USEC").ok()?.parse().ok()?;
+    Some(Duration::from_micros(usec))
+}
+
+// Ping the watchdog at half the interval, but only while the poller keeps completing rounds.
+// If the poller or DB writer stalls the pings stop and systemd restarts the service.
+pub fn spawn_watchdog() {
+    let interval = match watchdog_interval() {
+        Some(interval) => interval,
+        None => return,
+    };
+
+    let max_age = interval.as_secs().max(1) as i64;
+    let started = std::time::Instant::now();
+
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval / 2).await;
+
+            match seconds_since_progress() {
+                Some(age) if age < max_age => notify("WATCHDOG=1"),
+                // Startup grace: the first round may still be in flight
+                None if started.elapsed() < interval => notify("WATCHDOG=1"),
+                Some(age) => log_error!("Poller has not completed a round for {}s, withholding watchdog ping", age),
+                None => log_error!("Poller has not completed a round yet, withholding watchdog ping"),
+            }
+        }
+    });
+}
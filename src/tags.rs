@@ -0,0 +1,24 @@
+use std::collections::{BTreeMap, HashMap};
+
+/// Free-form endpoint tags, e.g. provider = "helius", tier = "paid"
+pub type Tags = BTreeMap<String, String>;
+
+/// Parse a `tags=provider:helius,tier:paid` query param into (key, value) pairs
+pub fn tag_filter(params: &HashMap<String, String>) -> Vec<(String, String)> {
+    params
+        .get("tags")
+        .map(|tags| {
+            tags.split(',')
+                .filter_map(|pair| pair.split_once(':'))
+                .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// True if `tags` carries every key/value pair in `filter`
+pub fn tags_match(tags: &Tags, filter: &[(String, String)]) -> bool {
+    filter
+        .iter()
+        .all(|(key, value)| tags.get(key) == Some(value))
+}
@@ -0,0 +1,124 @@
+use axum::{
+    body::{boxed, Full},
+    http::{header, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, SecondsFormat, TimeZone, Utc};
+use chrono_tz::Tz;
+use once_cell::sync::OnceCell;
+use serde::{de::Error, Deserialize, Deserializer};
+use serde_json::Value;
+
+// Fields holding unix seconds; each gets an ISO-8601 "<field>_iso" sibling in API responses
+const TIME_FIELDS: [&str; 2] = ["timestamp", "since"];
+
+// Fields holding unix milliseconds; "<name>_ms" gets a "<name>_iso" sibling
+const MILLIS_FIELDS: [&str; 6] = [
+    "timestamp_ms",
+    "since_ms",
+    "last_checked_ms",
+    "started_at_ms",
+    "ended_at_ms",
+    "opened_at_ms",
+];
+
+// Anything this large is unix milliseconds rather than seconds (year 5138 in seconds)
+pub const MILLIS_THRESHOLD: i64 = 100_000_000_000;
+
+static DISPLAY_TZ: OnceCell<Tz> = OnceCell::new();
+
+/// Set the timezone ISO-8601 strings are rendered in, e.g. "UTC" or "Europe/Berlin"
+pub fn set_display_timezone(name: &str) -> Result<(), String> {
+    let tz: Tz = name.parse().map_err(|e| format!("Invalid timezone {:?}: {}", name, e))?;
+    let _ = DISPLAY_TZ.set(tz);
+    Ok(())
+}
+
+/// Unix seconds (with millisecond precision) as ISO-8601 in the display timezone
+pub fn to_iso(secs: f64) -> Option<String> {
+    let millis = (secs * 1000.0).round() as i64;
+    let utc: DateTime<Utc> = Utc.timestamp_millis_opt(millis).single()?;
+    let tz = DISPLAY_TZ.get().copied().unwrap_or(Tz::UTC);
+    Some(utc.with_timezone(&tz).to_rfc3339_opts(SecondsFormat::Millis, true))
+}
+
+/// Read a stored timestamp as unix milliseconds. Records written before timestamps were
+/// normalized hold float unix seconds, which are converted here.
+pub fn deserialize_millis<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    let value = f64::deserialize(deserializer)?;
+    if !value.is_finite() {
+        return Err(D::Error::custom("timestamp must be a finite number"));
+    }
+    Ok(if value.abs() >= MILLIS_THRESHOLD as f64 { value as i64 } else { (value * 1000.0).round() as i64 })
+}
+
+/// `deserialize_millis` for optional timestamps
+pub fn deserialize_millis_opt<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<i64>, D::Error> {
+    #[derive(Deserialize)]
+    struct Millis(#[serde(deserialize_with = "deserialize_millis")] i64);
+
+    Ok(Option::<Millis>::deserialize(deserializer)?.map(|Millis(millis)| millis))
+}
+
+/// Parse a `from`/`to` query parameter into unix seconds. Accepts unix seconds, unix
+/// milliseconds and ISO-8601 / RFC 3339 strings.
+pub fn parse_time(value: &str) -> Option<i64> {
+    let value = value.trim();
+    if let Ok(number) = value.parse::<i64>() {
+        return Some(if number.abs() >= MILLIS_THRESHOLD { number / 1000 } else { number });
+    }
+    if let Ok(secs) = value.parse::<f64>() {
+        return secs.is_finite().then(|| secs as i64);
+    }
+    DateTime::parse_from_rfc3339(value).ok().map(|time| time.timestamp())
+}
+
+fn annotate(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            let seconds = TIME_FIELDS.iter().filter_map(|field| {
+                let iso = to_iso(map.get(*field)?.as_f64()?)?;
+                Some((format!("{}_iso", field), Value::String(iso)))
+            });
+            let millis = MILLIS_FIELDS.iter().filter_map(|field| {
+                let iso = to_iso(map.get(*field)?.as_f64()? / 1000.0)?;
+                Some((format!("{}_iso", field.strip_suffix("_ms")?), Value::String(iso)))
+            });
+            let additions: Vec<(String, Value)> = seconds.chain(millis).collect();
+            for child in map.values_mut() {
+                annotate(child);
+            }
+            map.extend(additions);
+        }
+        Value::Array(items) => items.iter_mut().for_each(annotate),
+        _ => {}
+    }
+}
+
+// Stored records hold unix milliseconds, a few computed views unix seconds; JSON API
+// responses additionally get ISO-8601 strings for both
+pub async fn add_iso_timestamps<B>(request: Request<B>, next: Next<B>) -> Response {
+    let response = next.run(request).await;
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .map_or(false, |content_type| content_type.as_bytes().starts_with(b"application/json"));
+    if !is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match hyper::body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    let mut value: Value = match serde_json::from_slice(&bytes) {
+        Ok(value) => value,
+        Err(_) => return Response::from_parts(parts, boxed(Full::from(bytes))),
+    };
+    annotate(&mut value);
+    let body = serde_json::to_vec(&value).unwrap_or_else(|_| bytes.to_vec());
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, boxed(Full::from(body)))
+}
@@ -0,0 +1,177 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{TimeZone, Utc};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::access::{allowed, resolve_scope};
+use crate::models::RpcEndpoint;
+use crate::state::AppState;
+use crate::storage::{scan_prefix, Store, USAGE_PREFIX};
+
+// Share of the daily budget after which an endpoint is only probed once per BUDGET_THROTTLE
+const BUDGET_SOFT_LIMIT: f64 = 0.8;
+const BUDGET_THROTTLE: Duration = Duration::from_secs(60);
+
+// Requests sent per (nickname, hour start); persisted so budgets survive restarts
+static REQUESTS: Lazy<Mutex<BTreeMap<(String, i64), u64>>> = Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+// When each throttled endpoint was last allowed through
+static LAST_ALLOWED: Lazy<Mutex<HashMap<String, Instant>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn hour_start(ts: i64) -> i64 {
+    ts.div_euclid(3600) * 3600
+}
+
+fn day_start(ts: i64) -> i64 {
+    ts.div_euclid(86400) * 86400
+}
+
+/// Count requests sent to an endpoint
+pub fn record_requests(nickname: &str, count: u64) {
+    let hour = hour_start(Utc::now().timestamp());
+    *REQUESTS
+        .lock()
+        .unwrap()
+        .entry((nickname.to_string(), hour))
+        .or_insert(0) += count;
+}
+
+fn requests_since(nickname: &str, since: i64) -> u64 {
+    REQUESTS
+        .lock()
+        .unwrap()
+        .range((nickname.to_string(), since)..=(nickname.to_string(), i64::MAX))
+        .map(|(_, count)| count)
+        .sum()
+}
+
+/// Whether an endpoint may be probed now: always without a budget, at most once per
+/// minute past 80% of its daily budget, and not at all once the budget (UTC day) is spent
+pub fn allow_probe(endpoint: &RpcEndpoint) -> bool {
+    let budget = match endpoint.daily_budget {
+        Some(budget) => budget,
+        None => return true,
+    };
+    let used = requests_since(&endpoint.nickname, day_start(Utc::now().timestamp()));
+    if used >= budget {
+        return false;
+    }
+    if (used as f64) < budget as f64 * BUDGET_SOFT_LIMIT {
+        return true;
+    }
+
+    let mut last_allowed = LAST_ALLOWED.lock().unwrap();
+    match last_allowed.get(&endpoint.nickname) {
+        Some(last) if last.elapsed() < BUDGET_THROTTLE => false,
+        _ => {
+            last_allowed.insert(endpoint.nickname.clone(), Instant::now());
+            true
+        }
+    }
+}
+
+/// Whether the endpoint has used up its daily budget (UTC day); unlike `allow_probe` this
+/// has no side effects, so long-lived connections can poll it
+pub fn budget_spent(endpoint: &RpcEndpoint) -> bool {
+    endpoint
+        .daily_budget
+        .map_or(false, |budget| requests_since(&endpoint.nickname, day_start(Utc::now().timestamp())) >= budget)
+}
+
+// Usage records are keyed as "!usage:<nickname>:<hour start>"
+fn usage_key(nickname: &str, hour: i64) -> String {
+    format!("{}{}:{}", USAGE_PREFIX, nickname, hour)
+}
+
+/// Restore persisted counters at startup
+pub fn load(db: &Store) {
+    let mut requests = REQUESTS.lock().unwrap();
+    for (key, count) in scan_prefix::<u64>(db, USAGE_PREFIX) {
+        let key = String::from_utf8_lossy(&key);
+        if let Some((nickname, hour)) = key[USAGE_PREFIX.len()..].rsplit_once(':') {
+            if let Ok(hour) = hour.parse::<i64>() {
+                requests.insert((nickname.to_string(), hour), count);
+            }
+        }
+    }
+}
+
+// Persist the current and previous hour's counters every minute, and forget hours older
+// than the retention window (the retention pass drops the stored copies)
+pub fn spawn_flush(db: Arc<Store>, keep_secs: i64) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            let now = Utc::now().timestamp();
+            let recent = hour_start(now) - 3600;
+            let mut requests = REQUESTS.lock().unwrap();
+            requests.retain(|(_, hour), _| *hour >= now - keep_secs);
+            for ((nickname, hour), count) in requests.iter().filter(|((_, hour), _)| *hour >= recent) {
+                if let Err(e) = db.put(usage_key(nickname, *hour).as_bytes(), count.to_string().as_bytes()) {
+                    log_error!("Failed to store request usage for {}: {}", nickname, e);
+                }
+            }
+        }
+    });
+}
+
+#[derive(Debug, Serialize)]
+pub struct UsageReport {
+    pub nickname: String,
+    pub daily_budget: Option<u64>,
+    pub this_hour: u64,
+    pub today: u64,
+    /// Requests per hour for the last 24 hours, keyed by hour start (unix seconds)
+    pub hourly: BTreeMap<i64, u64>,
+    /// Requests per UTC day, keyed by date
+    pub daily: BTreeMap<String, u64>,
+}
+
+// Probe requests sent per endpoint, per hour and per day
+pub async fn get_usage(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<UsageReport>>, StatusCode> {
+    let scope = resolve_scope(&state, &headers)?;
+    let now = Utc::now().timestamp();
+    let requests = REQUESTS.lock().unwrap();
+
+    let reports = state
+        .config
+        .rpc
+        .endpoints
+        .iter()
+        .filter(|endpoint| allowed(&scope, &endpoint.nickname, &endpoint.tags))
+        .map(|endpoint| {
+            let mut hourly = BTreeMap::new();
+            let mut daily: BTreeMap<String, u64> = BTreeMap::new();
+            for ((_, hour), count) in requests.range((endpoint.nickname.clone(), i64::MIN)..=(endpoint.nickname.clone(), i64::MAX)) {
+                if *hour >= hour_start(now) - 23 * 3600 {
+                    hourly.insert(*hour, *count);
+                }
+                let date = Utc.timestamp_opt(*hour, 0).unwrap().format("%Y-%m-%d").to_string();
+                *daily.entry(date).or_insert(0) += count;
+            }
+            UsageReport {
+                nickname: endpoint.nickname.clone(),
+                daily_budget: endpoint.daily_budget,
+                this_hour: hourly.get(&hour_start(now)).copied().unwrap_or(0),
+                today: daily
+                    .get(&Utc::now().format("%Y-%m-%d").to_string())
+                    .copied()
+                    .unwrap_or(0),
+                hourly,
+                daily,
+            }
+        })
+        .collect();
+
+    Ok(Json(reports))
+}
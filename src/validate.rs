@@ -0,0 +1,222 @@
+use reqwest::Url;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::check::sample_errors;
+use crate::config::{config_path, load_config, AppConfig};
+use crate::models::EndpointKind;
+use crate::rpc::probe_round;
+
+// Exit codes of `validate`
+const EXIT_OK: i32 = 0;
+const EXIT_INVALID: i32 = 1;
+const EXIT_UNPARSABLE: i32 = 2;
+const EXIT_UNREACHABLE: i32 = 3;
+
+// Problems that stop the monitor from working as configured, and ones that are merely suspicious
+#[derive(Default)]
+struct Findings {
+    errors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+fn check_url(findings: &mut Findings, what: &str, url: &str, schemes: &[&str]) {
+    match Url::parse(url) {
+        Ok(parsed) if schemes.contains(&parsed.scheme()) => {}
+        Ok(parsed) => findings.errors.push(format!(
+            "{}: scheme {:?} is not one of {}",
+            what,
+            parsed.scheme(),
+            schemes.join(", ")
+        )),
+        Err(e) => findings.errors.push(format!("{}: invalid URL {:?} ({})", what, url, e)),
+    }
+}
+
+fn check_file(findings: &mut Findings, what: &str, path: &str) {
+    if !Path::new(path).is_file() {
+        findings.errors.push(format!("{}: file {:?} not found", what, path));
+    }
+}
+
+fn check_endpoints(config: &AppConfig, findings: &mut Findings) {
+    let endpoints = &config.rpc.endpoints;
+    if endpoints.is_empty() {
+        findings.errors.push("[rpc]: no endpoints configured".to_string());
+    }
+
+    let mut seen = HashSet::new();
+    for endpoint in endpoints {
+        let name = format!("endpoint {:?}", endpoint.nickname);
+        // Nicknames are used in storage keys ("<nickname>:<ts>") and sample labels ("<nickname>@<region>")
+        if endpoint.nickname.is_empty() || endpoint.nickname.contains(&[':', '@', '/'][..]) {
+            findings.errors.push(format!("{}: nickname must be non-empty and free of ':', '@' and '/'", name));
+        }
+        if !seen.insert(endpoint.nickname.as_str()) {
+            findings.errors.push(format!("{}: duplicate nickname", name));
+        }
+
+        check_url(findings, &format!("{} url", name), &endpoint.url, &["http", "https"]);
+        if let Some(ws_url) = &endpoint.ws_url {
+            check_url(findings, &format!("{} ws_url", name), ws_url, &["ws", "wss"]);
+        }
+        if let Some(proxy) = &endpoint.proxy {
+            check_url(findings, &format!("{} proxy", name), proxy, &["http", "https", "socks5", "socks5h"]);
+        }
+
+        for (field, path) in [
+            ("ca_cert", &endpoint.ca_cert),
+            ("client_cert", &endpoint.client_cert),
+            ("client_key", &endpoint.client_key),
+        ] {
+            if let Some(path) = path {
+                check_file(findings, &format!("{} {}", name, field), path);
+            }
+        }
+        if endpoint.client_key.is_some() && endpoint.client_cert.is_none() {
+            findings.errors.push(format!("{}: client_key is set without client_cert", name));
+        }
+
+        if let Some(tpu) = &endpoint.tpu {
+            let valid = tpu
+                .rsplit_once(':')
+                .map_or(false, |(host, port)| !host.is_empty() && port.parse::<u16>().is_ok());
+            if !valid {
+                findings.errors.push(format!("{} tpu: expected \"host:port\", got {:?}", name, tpu));
+            }
+        }
+
+        if endpoint.kind == EndpointKind::Jito {
+            if endpoint.test_capable {
+                findings.errors.push(format!("{}: Jito endpoints cannot send test transactions (test_capable)", name));
+            }
+            if endpoint.archival {
+                findings.warnings.push(format!("{}: archival is ignored for Jito endpoints", name));
+            }
+        }
+        if endpoint.daily_budget == Some(0) {
+            findings.warnings.push(format!("{}: daily_budget = 0 means it is never probed", name));
+        }
+    }
+}
+
+fn check_sections(config: &AppConfig, findings: &mut Findings) {
+    if let Some(proxy) = &config.rpc.proxy {
+        check_url(findings, "[rpc] proxy", proxy, &["http", "https", "socks5", "socks5h"]);
+    }
+    if let Some(timezone) = &config.server.timezone {
+        if timezone.parse::<chrono_tz::Tz>().is_err() {
+            findings.errors.push(format!("[server] timezone: unknown timezone {:?}", timezone));
+        }
+    }
+
+    let nicknames: HashSet<&str> = config.rpc.endpoints.iter().map(|e| e.nickname.as_str()).collect();
+    if let Some(discovery) = &config.discovery {
+        if !nicknames.contains(discovery.seed.as_str()) {
+            findings.errors.push(format!("[discovery] seed: no endpoint named {:?}", discovery.seed));
+        }
+    }
+    if let Some(agent) = &config.agent {
+        check_url(findings, "[agent] aggregator_url", &agent.aggregator_url, &["http", "https"]);
+        if config.aggregator.is_some() {
+            findings.warnings.push("[agent] and [aggregator] are both set; an agent only ships samples".to_string());
+        }
+    }
+    if let Some(confirmation) = &config.confirmation {
+        check_file(findings, "[confirmation] keypair", &confirmation.keypair);
+        if !config.rpc.endpoints.iter().any(|e| e.test_capable) {
+            findings.errors.push("[confirmation]: needs at least one endpoint with test_capable = true".to_string());
+        }
+    }
+    if let Some(reference) = &config.cluster_reference {
+        if reference.nodes.is_empty() {
+            findings.errors.push("[cluster_reference]: no nodes configured".to_string());
+        }
+        for node in &reference.nodes {
+            check_url(findings, &format!("[cluster_reference] node {}", node.identity), &node.url, &["http", "https"]);
+        }
+    }
+
+    if !config.access.public() && config.access.keys.is_empty() && config.admin.is_none() {
+        findings.warnings.push("[access] public = false without keys or an admin token rejects every API request".to_string());
+    }
+    if config.access.anonymize() && config.admin.is_none() {
+        findings.warnings.push("[access] anonymize = true without an admin token hides the real names from everyone".to_string());
+    }
+
+    if config.storage.in_memory() {
+        let report_dir = config.reports.as_ref().map_or(false, |reports| reports.output_dir.is_some());
+        if config.storage.path.is_some() || config.storage.backup_dir.is_some() || config.logging.file.is_some() || report_dir {
+            findings.warnings.push(
+                "[storage] mode = \"memory\" ignores path, backup_dir, [logging] file and [reports] output_dir; nothing is written to disk".to_string(),
+            );
+        }
+        if config.storage.max_samples() == 0 {
+            findings.errors.push("[storage] max_samples must be above 0 in memory mode".to_string());
+        }
+    }
+
+    let retention = &config.retention;
+    if retention.raw_secs() > retention.minute_secs() || retention.minute_secs() > retention.hour_secs() {
+        findings.warnings.push("[retention]: rollups are kept for less time than the data they summarize".to_string());
+    }
+}
+
+// Parse the config file and look for mistakes that would otherwise surface only at runtime.
+// With `probe`, also send each endpoint one round of requests. Returns the exit code.
+pub async fn run_validate(cli_path: Option<&str>, probe: bool) -> i32 {
+    let path: PathBuf = match config_path(cli_path) {
+        Ok(path) => path,
+        Err(e) => {
+            println!("❌ {}", e);
+            return EXIT_UNPARSABLE;
+        }
+    };
+    let config = match load_config(&path) {
+        Ok(config) => config,
+        Err(e) => {
+            println!("❌ {}: {}", path.display(), e);
+            return EXIT_UNPARSABLE;
+        }
+    };
+
+    let mut findings = Findings::default();
+    check_endpoints(&config, &mut findings);
+    check_sections(&config, &mut findings);
+
+    for warning in &findings.warnings {
+        println!("⚠️  {}", warning);
+    }
+    for error in &findings.errors {
+        println!("❌ {}", error);
+    }
+    if !findings.errors.is_empty() {
+        println!("{} error(s), {} warning(s)", findings.errors.len(), findings.warnings.len());
+        return EXIT_INVALID;
+    }
+    println!(
+        "✅ {} is valid ({} endpoints, {} warning(s))",
+        path.display(),
+        config.rpc.endpoints.len(),
+        findings.warnings.len()
+    );
+
+    if !probe {
+        return EXIT_OK;
+    }
+    let mut unreachable = 0;
+    for sample in probe_round(&config.rpc.endpoints).await {
+        let errors = sample_errors(&sample);
+        if errors.is_empty() {
+            println!("✅ {} answered in {}ms", sample.nickname, sample.latency_ms);
+        } else {
+            println!("❌ {}: {}", sample.nickname, errors.join(", "));
+            unreachable += 1;
+        }
+    }
+    if unreachable > 0 {
+        EXIT_UNREACHABLE
+    } else {
+        EXIT_OK
+    }
+}
@@ -0,0 +1,210 @@
+use base64::Engine;
+use percent_encoding::percent_decode_str;
+use reqwest::Url;
+use rustls::crypto::ring;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use tokio_tungstenite::{client_async_tls_with_config, Connector, MaybeTlsStream, WebSocketStream};
+
+use crate::models::RpcEndpoint;
+use crate::rpc::DEFAULT_CONNECT_TIMEOUT_MS;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+// Any stream a WebSocket can run over: plain TCP or a tunnel through a proxy
+pub trait Io: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Io for T {}
+
+pub type Socket = WebSocketStream<MaybeTlsStream<Box<dyn Io>>>;
+
+// Rustls config trusting the webpki roots (as reqwest does) plus the endpoint's `ca_cert`,
+// presenting its `client_cert` when set
+fn tls_config(endpoint: &RpcEndpoint) -> Result<rustls::ClientConfig, BoxError> {
+    let mut roots = rustls::RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    if let Some(path) = &endpoint.ca_cert {
+        for cert in CertificateDer::pem_file_iter(path).map_err(|e| format!("Failed to read {}: {}", path, e))? {
+            let cert = cert.map_err(|e| format!("Invalid CA certificate in {}: {}", path, e))?;
+            roots.add(cert).map_err(|e| format!("Invalid CA certificate in {}: {}", path, e))?;
+        }
+    }
+
+    let builder = rustls::ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()?
+        .with_root_certificates(roots);
+
+    match &endpoint.client_cert {
+        Some(path) => {
+            let certs = CertificateDer::pem_file_iter(path)
+                .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+                .map_err(|e| format!("Invalid client certificate {}: {}", path, e))?;
+            let key_path = endpoint.client_key.as_deref().unwrap_or(path);
+            let key = PrivateKeyDer::from_pem_file(key_path).map_err(|e| format!("Invalid client key {}: {}", key_path, e))?;
+            Ok(builder.with_client_auth_cert(certs, key)?)
+        }
+        None => Ok(builder.with_no_client_auth()),
+    }
+}
+
+// `host:port` as it appears in a CONNECT request line
+fn authority(host: &str, port: u16) -> String {
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V6(ip)) => format!("[{}]:{}", ip, port),
+        _ => format!("{}:{}", host, port),
+    }
+}
+
+fn credentials(proxy: &Url) -> Option<(String, String)> {
+    if proxy.username().is_empty() {
+        return None;
+    }
+    let decode = |value: &str| percent_decode_str(value).decode_utf8_lossy().into_owned();
+    Some((decode(proxy.username()), decode(proxy.password().unwrap_or(""))))
+}
+
+// Open a tunnel with HTTP CONNECT
+async fn http_connect<S: AsyncRead + AsyncWrite + Unpin>(mut stream: S, proxy: &Url, host: &str, port: u16) -> Result<S, BoxError> {
+    let target = authority(host, port);
+    let mut request = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n", target);
+    if let Some((user, password)) = credentials(proxy) {
+        let token = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", user, password));
+        request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", token));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    // Read the response head byte by byte so nothing past it is consumed
+    let mut head = Vec::new();
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() > 8192 {
+            return Err("proxy response header too large".into());
+        }
+        head.push(stream.read_u8().await?);
+    }
+    let head = String::from_utf8_lossy(&head);
+    let status_line = head.lines().next().unwrap_or_default();
+    match status_line.split_whitespace().nth(1) {
+        Some(status) if status.starts_with('2') => Ok(stream),
+        _ => Err(format!("proxy refused CONNECT to {}: {}", target, status_line).into()),
+    }
+}
+
+// Open a tunnel with SOCKS5 (RFC 1928), with username/password auth (RFC 1929) if the URL has credentials
+async fn socks5_connect(mut stream: TcpStream, proxy: &Url, host: &str, port: u16) -> Result<TcpStream, BoxError> {
+    let credentials = credentials(proxy);
+    let method = if credentials.is_some() { 0x02 } else { 0x00 };
+    stream.write_all(&[0x05, 0x01, method]).await?;
+    let mut reply = [0u8; 2];
+    stream.read_exact(&mut reply).await?;
+    if reply[1] != method {
+        return Err("SOCKS5 proxy rejected the authentication method".into());
+    }
+
+    if let Some((user, password)) = credentials {
+        let mut auth = vec![0x01, user.len() as u8];
+        auth.extend_from_slice(user.as_bytes());
+        auth.push(password.len() as u8);
+        auth.extend_from_slice(password.as_bytes());
+        stream.write_all(&auth).await?;
+        stream.read_exact(&mut reply).await?;
+        if reply[1] != 0x00 {
+            return Err("SOCKS5 proxy rejected the credentials".into());
+        }
+    }
+
+    // socks5:// resolves the hostname locally, socks5h:// leaves it to the proxy
+    let ip = match host.parse::<IpAddr>() {
+        Ok(ip) => Some(ip),
+        Err(_) if proxy.scheme() == "socks5" => tokio::net::lookup_host((host, port)).await?.next().map(|addr| addr.ip()),
+        Err(_) => None,
+    };
+    let mut request = vec![0x05, 0x01, 0x00];
+    match ip {
+        Some(IpAddr::V4(ip)) => {
+            request.push(0x01);
+            request.extend_from_slice(&ip.octets());
+        }
+        Some(IpAddr::V6(ip)) => {
+            request.push(0x04);
+            request.extend_from_slice(&ip.octets());
+        }
+        None => {
+            request.push(0x03);
+            request.push(host.len() as u8);
+            request.extend_from_slice(host.as_bytes());
+        }
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).await?;
+
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header).await?;
+    if header[1] != 0x00 {
+        return Err(format!("SOCKS5 proxy refused the connection to {}:{} (reply {})", host, port, header[1]).into());
+    }
+    let address_len = match header[3] {
+        0x01 => 4,
+        0x04 => 16,
+        0x03 => stream.read_u8().await? as usize,
+        other => return Err(format!("SOCKS5 proxy sent an unknown address type {}", other).into()),
+    };
+    let mut bound = vec![0u8; address_len + 2];
+    stream.read_exact(&mut bound).await?;
+    Ok(stream)
+}
+
+async fn open_stream(endpoint: &RpcEndpoint, host: &str, port: u16) -> Result<Box<dyn Io>, BoxError> {
+    // A pinned IP replaces DNS, both for direct connections and for the target sent to a proxy
+    let resolved = endpoint.resolve_ip.map(|ip| ip.to_string());
+    let target = resolved.as_deref().unwrap_or(host);
+
+    let proxy = match &endpoint.proxy {
+        Some(proxy) => Url::parse(proxy).map_err(|e| format!("Invalid proxy {}: {}", proxy, e))?,
+        None => return Ok(Box::new(TcpStream::connect((target, port)).await?)),
+    };
+    let proxy_host = proxy.host_str().ok_or_else(|| format!("Proxy URL has no host: {}", proxy))?;
+    let proxy_port = proxy.port_or_known_default().unwrap_or(1080);
+    let stream = TcpStream::connect((proxy_host, proxy_port)).await?;
+
+    match proxy.scheme() {
+        "http" => Ok(Box::new(http_connect(stream, &proxy, target, port).await?)),
+        "https" => {
+            let connector = TlsConnector::from(Arc::new(tls_config(endpoint)?));
+            let stream = connector.connect(ServerName::try_from(proxy_host.to_string())?, stream).await?;
+            Ok(Box::new(http_connect(stream, &proxy, target, port).await?))
+        }
+        "socks5" | "socks5h" => Ok(Box::new(socks5_connect(stream, &proxy, target, port).await?)),
+        scheme => Err(format!("Unsupported proxy scheme: {}", scheme).into()),
+    }
+}
+
+/// Open a WebSocket to `url` with the same `resolve_ip`, `proxy`, `ca_cert`/`client_cert`
+/// and connect timeout the endpoint's HTTP client uses
+pub async fn connect(endpoint: &RpcEndpoint, url: &str) -> Result<Socket, BoxError> {
+    let parsed = Url::parse(url).map_err(|e| format!("Invalid WebSocket URL {}: {}", url, e))?;
+    let host = parsed.host_str().ok_or_else(|| format!("WebSocket URL has no host: {}", url))?;
+    let port = parsed
+        .port_or_known_default()
+        .ok_or_else(|| format!("WebSocket URL has no port: {}", url))?;
+    let connector = match parsed.scheme() {
+        "wss" => Connector::Rustls(Arc::new(tls_config(endpoint)?)),
+        _ => Connector::Plain,
+    };
+
+    let timeout = Duration::from_millis(endpoint.connect_timeout_ms.unwrap_or(DEFAULT_CONNECT_TIMEOUT_MS));
+    let handshake = async {
+        let stream = open_stream(endpoint, host, port).await?;
+        let (socket, _) = client_async_tls_with_config(url, stream, None, Some(connector)).await?;
+        Ok::<_, BoxError>(socket)
+    };
+    tokio::time::timeout(timeout, handshake)
+        .await
+        .map_err(|_| format!("WebSocket connect to {} timed out", url))?
+}